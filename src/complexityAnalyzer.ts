@@ -297,6 +297,59 @@ export class ComplexityAnalyzer {
         };
     }
 
+    /**
+     * Maps any complexity the analyzer reports, such as `O(n·m)`, `O(3ⁿ)`,
+     * `O(n^5)` or `O(V + E)`, to the closest of the nine classes below,
+     * judged by its fastest-growing term.
+     */
+    static getComplexityClass(complexity: string): string {
+        const classes = ['O(1)', 'O(log n)', 'O(n)', 'O(n log n)', 'O(n²)', 'O(n³)', 'O(n^k)', 'O(2ⁿ)', 'O(n!)'];
+        // As a result, `O(n^k)` is `n` to a variable power, which the
+        // analyzer ranks as exponential in `k` rather than polynomial
+        if (classes.includes(complexity) && complexity !== 'O(n^k)') {
+            return complexity;
+        }
+        const inner = complexity.trim().replace(/^O\((.*)\)$/, '$1');
+        const ranks = inner.split(' + ').map(term => {
+            if (term.includes('!')) {
+                return 8;
+            }
+            if (/ⁿ|\^\(|\^[A-Za-z]/.test(term)) {
+                return 7;
+            }
+            const logs = /log\S*\s+\w+/g;
+            const hasLog = logs.test(term);
+            const degree = term
+                .replace(logs, '')
+                .split('·')
+                .map(factor => factor.trim())
+                .filter(factor => factor !== '' && factor !== '1')
+                .reduce((sum, factor) => sum + ComplexityAnalyzer.factorDegree(factor), 0);
+            if (degree === 0) {
+                return hasLog ? 1 : 0;
+            }
+            if (degree === 1) {
+                return hasLog ? 3 : 2;
+            }
+            return Math.min(degree + 2, 6);
+        });
+        return classes[Math.max(0, ...ranks)];
+    }
+
+    /** Degree of one factor: `n` is 1, `m²` is 2, `n^5` is 5. */
+    private static factorDegree(factor: string): number {
+        const superscripts = '⁰¹²³⁴⁵⁶⁷⁸⁹';
+        const written = factor.match(/\^(\d+)$/);
+        if (written) {
+            return parseInt(written[1], 10);
+        }
+        const digits = [...factor].filter(c => superscripts.includes(c));
+        if (digits.length === 0) {
+            return 1;
+        }
+        return digits.reduce((value, c) => value * 10 + superscripts.indexOf(c), 0);
+    }
+
     static getComplexityOrder(complexity: string): number {
         const order: { [key: string]: number } = {
             'O(1)': 0,
@@ -309,7 +362,7 @@ export class ComplexityAnalyzer {
             'O(2ⁿ)': 7,
             'O(n!)': 8
         };
-        return order[complexity] ?? 2; // Default to O(n)
+        return order[ComplexityAnalyzer.getComplexityClass(complexity)] ?? 2; // Default to O(n)
     }
 
     static getComplexityColor(complexity: string): string {
@@ -324,7 +377,7 @@ export class ComplexityAnalyzer {
             'O(2ⁿ)': '#343a40',      // Dark
             'O(n!)': '#000000'       // Black
         };
-        return colors[complexity] ?? '#6c757d';
+        return colors[ComplexityAnalyzer.getComplexityClass(complexity)] ?? '#6c757d';
     }
 
     static getComplexityDescription(complexity: string): string {
@@ -339,6 +392,6 @@ export class ComplexityAnalyzer {
             'O(2ⁿ)': 'Exponential time - unacceptable for large inputs',
             'O(n!)': 'Factorial time - only suitable for tiny inputs'
         };
-        return descriptions[complexity] ?? 'Linear time complexity (estimated)';
+        return descriptions[ComplexityAnalyzer.getComplexityClass(complexity)] ?? 'Linear time complexity (estimated)';
     }
 }
\ No newline at end of file
@@ -4,37 +4,37 @@ import { AnalysisResult, ComplexityAnalyzer } from './complexityAnalyzer';
 export class DecorationProvider {
     private decorationTypes: Map<string, vscode.TextEditorDecorationType> = new Map();
 
-    constructor() {
-        this.initializeDecorationTypes();
-    }
+    // Decoration types are created per complexity string on first use, since
+    // the analyzer reports open-ended forms such as `O(n·m)` or `O(3ⁿ)`
+    private getDecorationType(complexity: string): vscode.TextEditorDecorationType {
+        const existing = this.decorationTypes.get(complexity);
+        if (existing) {
+            return existing;
+        }
 
-    private initializeDecorationTypes() {
-        const complexities = ['O(1)', 'O(log n)', 'O(n)', 'O(n log n)', 'O(n²)', 'O(n³)', 'O(n^k)', 'O(2ⁿ)', 'O(n!)'];
-        
-        complexities.forEach(complexity => {
-            const color = ComplexityAnalyzer.getComplexityColor(complexity);
-            const decorationType = vscode.window.createTextEditorDecorationType({
-                after: {
-                    contentText: ` ${complexity}`,
-                    color: color,
-                    fontWeight: 'bold',
-                    margin: '0 0 0 1em'
-                },
-                rangeBehavior: vscode.DecorationRangeBehavior.ClosedClosed,
-                overviewRulerColor: color,
-                overviewRulerLane: vscode.OverviewRulerLane.Right,
-                light: {
-                    border: `1px solid ${color}30`,
-                    backgroundColor: `${color}10`
-                },
-                dark: {
-                    border: `1px solid ${color}50`,
-                    backgroundColor: `${color}20`
-                }
-            });
-            
-            this.decorationTypes.set(complexity, decorationType);
+        const color = ComplexityAnalyzer.getComplexityColor(complexity);
+        const decorationType = vscode.window.createTextEditorDecorationType({
+            after: {
+                contentText: ` ${complexity}`,
+                color: color,
+                fontWeight: 'bold',
+                margin: '0 0 0 1em'
+            },
+            rangeBehavior: vscode.DecorationRangeBehavior.ClosedClosed,
+            overviewRulerColor: color,
+            overviewRulerLane: vscode.OverviewRulerLane.Right,
+            light: {
+                border: `1px solid ${color}30`,
+                backgroundColor: `${color}10`
+            },
+            dark: {
+                border: `1px solid ${color}50`,
+                backgroundColor: `${color}20`
+            }
         });
+
+        this.decorationTypes.set(complexity, decorationType);
+        return decorationType;
     }
 
     public applyDecorations(editor: vscode.TextEditor | undefined, result: AnalysisResult) {
@@ -72,28 +72,23 @@ export class DecorationProvider {
 
         // Apply decorations by complexity type
         decorationMap.forEach((decorations, complexity) => {
-            const decorationType = this.decorationTypes.get(complexity);
-            if (decorationType) {
-                editor.setDecorations(decorationType, decorations);
-            }
+            editor.setDecorations(this.getDecorationType(complexity), decorations);
         });
 
         // Apply overall complexity decoration to the first line
         if (result.overall && editor.document.lineCount > 0) {
-            const overallDecorationType = this.decorationTypes.get(result.overall);
-            if (overallDecorationType) {
-                const overallRange = new vscode.Range(
-                    new vscode.Position(0, 0),
-                    new vscode.Position(0, 0)
-                );
-
-                const overallDecoration: vscode.DecorationOptions = {
-                    range: overallRange,
-                    hoverMessage: this.createOverallHoverMessage(result)
-                };
-
-                editor.setDecorations(overallDecorationType, [overallDecoration]);
-            }
+            const overallDecorationType = this.getDecorationType(result.overall);
+            const overallRange = new vscode.Range(
+                new vscode.Position(0, 0),
+                new vscode.Position(0, 0)
+            );
+
+            const overallDecoration: vscode.DecorationOptions = {
+                range: overallRange,
+                hoverMessage: this.createOverallHoverMessage(result)
+            };
+
+            editor.setDecorations(overallDecorationType, [overallDecoration]);
         }
     }
 
@@ -315,6 +315,9 @@ export class ReportProvider implements vscode.WebviewViewProvider {
             <script>
                 const vscode = acquireVsCodeApi();
                 const analysisData = ${JSON.stringify(this._lastResult)};
+                // Colors and ranks come from the extension, which reads every complexity format
+                const complexityColors = ${JSON.stringify(Object.fromEntries(this._lastResult.functions.map(f => [f.complexity, ComplexityAnalyzer.getComplexityColor(f.complexity)])))};
+                const complexityOrders = ${JSON.stringify(Object.fromEntries(this._lastResult.functions.map(f => [f.complexity, ComplexityAnalyzer.getComplexityOrder(f.complexity)])))};
 
                 function exportReport() {
                     vscode.postMessage({ command: 'export' });
@@ -445,33 +448,11 @@ export class ReportProvider implements vscode.WebviewViewProvider {
                 }
 
                 function getComplexityColor(complexity) {
-                    const colors = {
-                        'O(1)': '#28a745',
-                        'O(log n)': '#20c997',
-                        'O(n)': '#ffc107',
-                        'O(n log n)': '#fd7e14',
-                        'O(n²)': '#dc3545',
-                        'O(n³)': '#6f42c1',
-                        'O(n^k)': '#e83e8c',
-                        'O(2ⁿ)': '#343a40',
-                        'O(n!)': '#000000'
-                    };
-                    return colors[complexity] || '#6c757d';
+                    return complexityColors[complexity] || '#6c757d';
                 }
 
                 function getComplexityOrder(complexity) {
-                    const order = {
-                        'O(1)': 0,
-                        'O(log n)': 1,
-                        'O(n)': 2,
-                        'O(n log n)': 3,
-                        'O(n²)': 4,
-                        'O(n³)': 5,
-                        'O(n^k)': 6,
-                        'O(2ⁿ)': 7,
-                        'O(n!)': 8
-                    };
-                    return order[complexity] || 0;
+                    return complexityOrders[complexity] || 0;
                 }
             </script>
         </body>
@@ -0,0 +1,175 @@
+//! Comments steering the analysis of a function.
+//!
+//! `complexity: ignore` leaves a function out of the results, `complexity:
+//! expect O(n log n)` warns when the analysis finds something else, and
+//! `complexity-assume n <= 100` costs loops over `n` as constant. A comment
+//! applies to the function whose header it sits on, to the function right
+//! below it, or else to the innermost function around it.
+
+use crate::FunctionInfo;
+use crate::complexity::Complexity;
+use crate::lexer::{Token, TokenKind};
+
+/// What the comments attached to one function ask for.
+#[derive(Debug, Clone, Default)]
+pub struct Annotations {
+    pub ignore: bool,
+    /// The expected complexity, with the line of its comment.
+    pub expect: Option<(Complexity, usize)>,
+    pub assumptions: Vec<Assumption>,
+}
+
+/// `n <= 100`: the variable never exceeds a constant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assumption {
+    /// Variable as written, such as `n` or `len(rows)`.
+    pub variable: String,
+    pub bound: String,
+}
+
+impl std::fmt::Display for Assumption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} <= {}", self.variable, self.bound)
+    }
+}
+
+enum Directive {
+    Ignore,
+    Expect(Complexity),
+    Assume(Assumption),
+}
+
+/// A directive with the lines of the comment holding it.
+struct Located {
+    line: usize,
+    end_line: usize,
+    directive: Directive,
+}
+
+/// Reads the directives in the comments of `tokens` and attaches each to
+/// its function. `code` is `tokens` without comments. Directives that
+/// cannot be read are reported in `warnings`.
+pub fn attach(
+    tokens: &[Token],
+    code: &[Token],
+    functions: &mut [FunctionInfo],
+    warnings: &mut Vec<String>,
+) {
+    for located in directives(tokens, warnings) {
+        let Some(position) = owner(&located, code, functions) else {
+            warnings.push(format!(
+                "Complexity comment at line {} is not in or above a function",
+                located.line
+            ));
+            continue;
+        };
+        let annotations = &mut functions[position].annotations;
+        match located.directive {
+            Directive::Ignore => annotations.ignore = true,
+            Directive::Expect(expected) => annotations.expect = Some((expected, located.line)),
+            Directive::Assume(assumption) => annotations.assumptions.push(assumption),
+        }
+    }
+}
+
+fn directives(tokens: &[Token], warnings: &mut Vec<String>) -> Vec<Located> {
+    let mut found = Vec::new();
+    for token in tokens.iter().filter(|t| t.kind == TokenKind::Comment) {
+        for (offset, text) in token.text.lines().enumerate() {
+            let line = token.line + offset;
+            let text = text
+                .trim()
+                .trim_start_matches(['/', '*', '#', '!'])
+                .trim_end_matches(['/', '*'])
+                .trim();
+            let directive = if let Some(rest) = strip_keyword(text, "complexity-assume") {
+                parse_assumption(rest)
+                    .map(Directive::Assume)
+                    .ok_or_else(|| format!("expected 'variable <= bound', found '{}'", rest))
+            } else if let Some(rest) = strip_keyword(text, "complexity:") {
+                parse_directive(rest)
+            } else {
+                continue;
+            };
+            match directive {
+                Ok(directive) => found.push(Located {
+                    line,
+                    end_line: token.end_line,
+                    directive,
+                }),
+                Err(message) => warnings.push(format!(
+                    "Complexity comment at line {} ignored: {}",
+                    line, message
+                )),
+            }
+        }
+    }
+    found
+}
+
+/// The text after `keyword`, when the comment starts with it.
+fn strip_keyword<'a>(text: &'a str, keyword: &str) -> Option<&'a str> {
+    let prefix = text.get(..keyword.len())?;
+    prefix
+        .eq_ignore_ascii_case(keyword)
+        .then(|| text[keyword.len()..].trim())
+}
+
+/// `ignore`, `expect O(n log n)` or `assume n <= 100`.
+fn parse_directive(text: &str) -> Result<Directive, String> {
+    let (word, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let rest = rest.trim();
+    match word.to_lowercase().as_str() {
+        "ignore" => Ok(Directive::Ignore),
+        "expect" => rest
+            .parse()
+            .map(Directive::Expect)
+            .map_err(|error| format!("{}", error)),
+        "assume" => parse_assumption(rest)
+            .map(Directive::Assume)
+            .ok_or_else(|| format!("expected 'variable <= bound', found '{}'", rest)),
+        _ => Err(format!("unknown directive '{}'", word)),
+    }
+}
+
+/// `n <= 100`, `len(rows) < 10`.
+fn parse_assumption(text: &str) -> Option<Assumption> {
+    let (variable, bound) = text
+        .split_once("<=")
+        .or_else(|| text.split_once('<'))
+        .or_else(|| text.split_once('≤'))?;
+    let variable = variable.trim();
+    let bound = bound.split_whitespace().next()?;
+    (!variable.is_empty()).then(|| Assumption {
+        variable: variable.to_string(),
+        bound: bound.to_string(),
+    })
+}
+
+/// The function a directive applies to, by position in `functions`.
+fn owner(located: &Located, code: &[Token], functions: &[FunctionInfo]) -> Option<usize> {
+    let innermost = |applies: &dyn Fn(&FunctionInfo) -> bool| {
+        functions
+            .iter()
+            .enumerate()
+            .filter(|(_, function)| applies(function))
+            .min_by_key(|(_, function)| function.end_line - function.start_line)
+            .map(|(position, _)| position)
+    };
+
+    // The first line of code below the comment, past any decorators
+    let mut after = located.end_line;
+    let below = loop {
+        match code.iter().find(|t| t.line > after) {
+            Some(token) if token.is_punct("@") => after = token.line,
+            Some(token) => break Some(token.line),
+            None => break None,
+        }
+    };
+
+    innermost(&|function| function.start_line == located.line)
+        .or_else(|| below.and_then(|line| innermost(&|function| function.start_line == line)))
+        .or_else(|| {
+            innermost(&|function| (function.start_line..=function.end_line).contains(&located.line))
+        })
+}
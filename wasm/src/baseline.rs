@@ -0,0 +1,167 @@
+//! Baselines of accepted complexity.
+//!
+//! A baseline records the complexity of every function at some point,
+//! keyed by file and function name so that it survives code moving around
+//! within a file. Comparing a later analysis against it reports only what
+//! got worse: functions slower than they were, and new functions over
+//! budget. Functions already over budget in the baseline are left alone.
+
+use crate::AnalysisResult;
+use crate::budget::{self, Budget};
+use crate::complexity::Complexity;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Baseline {
+    /// Complexity of each function, by file path and then function name.
+    /// Where a file defines a name twice, the slower function is kept.
+    files: BTreeMap<String, BTreeMap<String, Complexity>>,
+}
+
+/// A function slower than in the baseline, or new and over budget.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Regression {
+    path: String,
+    function: String,
+    line_start: usize,
+    line_end: usize,
+    complexity: Complexity,
+    /// Complexity in the baseline; `None` for a new function.
+    baseline: Option<Complexity>,
+    budget: Complexity,
+}
+
+/// Outcome of comparing an analysis against a baseline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineReport {
+    /// Number of functions compared.
+    checked: usize,
+    regressions: Vec<Regression>,
+}
+
+impl Baseline {
+    /// Records every function of `files`, each given with its path.
+    pub fn snapshot(files: &[(&str, &AnalysisResult)]) -> Self {
+        let mut baseline = Self::default();
+        for &(path, result) in files {
+            let functions = baseline.files.entry(budget::normalize(path)).or_default();
+            for function in result.functions() {
+                let complexity = function.complexity();
+                functions
+                    .entry(function.function().to_string())
+                    .and_modify(|kept| {
+                        if complexity.order(kept).is_gt() {
+                            *kept = complexity.clone();
+                        }
+                    })
+                    .or_insert_with(|| complexity.clone());
+            }
+        }
+        baseline
+    }
+
+    /// Complexity of `function` in the file at `path`, if it was recorded.
+    pub fn get(&self, path: &str, function: &str) -> Option<&Complexity> {
+        self.files.get(&budget::normalize(path))?.get(function)
+    }
+
+    /// Compares every function of `files` with the baseline: a recorded
+    /// function regresses when it grows faster than it did, and a new one
+    /// when it is over `budget`.
+    pub fn compare(&self, files: &[(&str, &AnalysisResult)], budget: &Budget) -> BaselineReport {
+        let mut checked = 0;
+        let mut regressions = Vec::new();
+        for &(path, result) in files {
+            for function in result.functions() {
+                checked += 1;
+                let complexity = function.complexity();
+                let (limit, _) = budget.limit(path, function.function());
+                let baseline = self.get(path, function.function());
+                let regressed = match baseline {
+                    Some(baseline) => complexity.order(baseline).is_gt(),
+                    None => complexity.order(limit).is_gt(),
+                };
+                if regressed {
+                    regressions.push(Regression {
+                        path: path.to_string(),
+                        function: function.function().to_string(),
+                        line_start: function.line_start(),
+                        line_end: function.line_end(),
+                        complexity: complexity.clone(),
+                        baseline: baseline.cloned(),
+                        budget: limit.clone(),
+                    });
+                }
+            }
+        }
+        BaselineReport {
+            checked,
+            regressions,
+        }
+    }
+}
+
+impl Regression {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn function(&self) -> &str {
+        &self.function
+    }
+
+    pub fn line_start(&self) -> usize {
+        self.line_start
+    }
+
+    pub fn line_end(&self) -> usize {
+        self.line_end
+    }
+
+    pub fn complexity(&self) -> &Complexity {
+        &self.complexity
+    }
+
+    /// Complexity in the baseline; `None` for a new function.
+    pub fn baseline(&self) -> Option<&Complexity> {
+        self.baseline.as_ref()
+    }
+
+    pub fn budget(&self) -> &Complexity {
+        &self.budget
+    }
+}
+
+impl fmt::Display for Regression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.baseline {
+            Some(baseline) => write!(
+                f,
+                "{}:{}: '{}' went from {} to {}",
+                self.path, self.line_start, self.function, baseline, self.complexity
+            ),
+            None => write!(
+                f,
+                "{}:{}: new function '{}' is {}, over its budget of {}",
+                self.path, self.line_start, self.function, self.complexity, self.budget
+            ),
+        }
+    }
+}
+
+impl BaselineReport {
+    pub fn checked(&self) -> usize {
+        self.checked
+    }
+
+    pub fn regressions(&self) -> &[Regression] {
+        &self.regressions
+    }
+
+    /// Whether nothing got worse.
+    pub fn passed(&self) -> bool {
+        self.regressions.is_empty()
+    }
+}
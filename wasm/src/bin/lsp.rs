@@ -0,0 +1,343 @@
+//! `big-o-analyser-lsp`: a language server over stdio.
+//!
+//! Analyzes every open document and publishes the complexity of each
+//! function as a code lens and a hover, and functions above the configured
+//! maximum as warnings. The maximum comes from the `maxComplexity`
+//! initialization option, `O(n log n)` by default or when the option cannot
+//! be read.
+
+use big_o_analyser::{AnalysisResult, Complexity, ComplexityAnalyzer, FunctionAnalysis};
+use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{CodeLensRequest, HoverRequest, Request as _};
+use lsp_types::{
+    CodeLens, CodeLensOptions, CodeLensParams, Command, Diagnostic, DiagnosticSeverity,
+    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams, Hover,
+    HoverContents, HoverParams, HoverProviderCapability, InitializeParams, MarkupContent,
+    MarkupKind, NumberOrString, Position, PublishDiagnosticsParams, Range, ServerCapabilities,
+    TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+
+/// Functions above this are reported unless configured otherwise.
+const DEFAULT_MAX_COMPLEXITY: &str = "O(n log n)";
+
+/// An open document with its latest analysis.
+struct Document {
+    text: String,
+    analysis: AnalysisResult,
+}
+
+struct Server {
+    connection: Connection,
+    max_complexity: Complexity,
+    documents: HashMap<Url, Document>,
+}
+
+fn main() -> Result<(), Box<dyn Error + Sync + Send>> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        code_lens_provider: Some(CodeLensOptions {
+            resolve_provider: Some(false),
+        }),
+        ..ServerCapabilities::default()
+    };
+    let params = connection.initialize(serde_json::to_value(capabilities)?)?;
+    let params: InitializeParams = serde_json::from_value(params)?;
+
+    let default: Complexity = DEFAULT_MAX_COMPLEXITY.parse()?;
+    let max_complexity = match params
+        .initialization_options
+        .as_ref()
+        .and_then(|options| options.get("maxComplexity"))
+        .and_then(|value| value.as_str())
+        .map(str::parse)
+    {
+        Some(Ok(max)) => max,
+        Some(Err(error)) => {
+            eprintln!("maxComplexity: {}; using {}", error, default);
+            default
+        }
+        None => default,
+    };
+
+    let mut server = Server {
+        connection,
+        max_complexity,
+        documents: HashMap::new(),
+    };
+    server.run()?;
+    drop(server);
+    io_threads.join()?;
+    Ok(())
+}
+
+impl Server {
+    fn run(&mut self) -> Result<(), Box<dyn Error + Sync + Send>> {
+        while let Ok(message) = self.connection.receiver.recv() {
+            match message {
+                Message::Request(request) => {
+                    if self.connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    self.handle_request(request)?;
+                }
+                Message::Notification(notification) => self.handle_notification(notification)?,
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Answers a request. Requests whose parameters cannot be read get an
+    /// `InvalidParams` error.
+    fn handle_request(&mut self, request: Request) -> Result<(), Box<dyn Error + Sync + Send>> {
+        let Request { id, method, params } = request;
+        let response = match method.as_str() {
+            HoverRequest::METHOD => match serde_json::from_value::<HoverParams>(params) {
+                Ok(params) => {
+                    let position = params.text_document_position_params;
+                    let hover = self
+                        .documents
+                        .get(&position.text_document.uri)
+                        .and_then(|document| hover(document, position.position));
+                    Response::new_ok(id, hover)
+                }
+                Err(error) => invalid_params(id, &method, &error),
+            },
+            CodeLensRequest::METHOD => match serde_json::from_value::<CodeLensParams>(params) {
+                Ok(params) => {
+                    let lenses = self
+                        .documents
+                        .get(&params.text_document.uri)
+                        .map(code_lenses)
+                        .unwrap_or_default();
+                    Response::new_ok(id, lenses)
+                }
+                Err(error) => invalid_params(id, &method, &error),
+            },
+            _ => method_not_found(id, &method),
+        };
+        self.connection.sender.send(response.into())?;
+        Ok(())
+    }
+
+    /// Applies a notification. Notifications whose parameters cannot be
+    /// read are logged and skipped.
+    fn handle_notification(
+        &mut self,
+        notification: Notification,
+    ) -> Result<(), Box<dyn Error + Sync + Send>> {
+        let Notification { method, params } = notification;
+        match method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let Some(params) =
+                    notification_params::<DidOpenTextDocumentParams>(&method, params)
+                else {
+                    return Ok(());
+                };
+                let document = params.text_document;
+                let language = language(&document.language_id, &document.uri);
+                self.update(document.uri, language, document.text)?;
+            }
+            DidChangeTextDocument::METHOD => {
+                let Some(params) =
+                    notification_params::<DidChangeTextDocumentParams>(&method, params)
+                else {
+                    return Ok(());
+                };
+                // Full sync: the last change holds the whole text
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    let uri = params.text_document.uri;
+                    let language = self
+                        .documents
+                        .get(&uri)
+                        .map(|document| document.analysis.language().to_string())
+                        .or_else(|| language("", &uri));
+                    self.update(uri, language, change.text)?;
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                let Some(params) =
+                    notification_params::<DidCloseTextDocumentParams>(&method, params)
+                else {
+                    return Ok(());
+                };
+                self.documents.remove(&params.text_document.uri);
+                self.publish(params.text_document.uri, Vec::new())?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Re-analyzes a document and publishes its diagnostics. Documents in
+    /// languages the analyzer does not know are ignored.
+    fn update(
+        &mut self,
+        uri: Url,
+        language: Option<String>,
+        text: String,
+    ) -> Result<(), Box<dyn Error + Sync + Send>> {
+        let Some(language) = language else {
+            return Ok(());
+        };
+        let analysis = ComplexityAnalyzer::new(&language).analyze(&text);
+        let document = Document { text, analysis };
+        let diagnostics = diagnostics(&document, &self.max_complexity);
+        self.documents.insert(uri.clone(), document);
+        self.publish(uri, diagnostics)
+    }
+
+    fn publish(
+        &self,
+        uri: Url,
+        diagnostics: Vec<Diagnostic>,
+    ) -> Result<(), Box<dyn Error + Sync + Send>> {
+        let params = PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        };
+        let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+        self.connection.sender.send(notification.into())?;
+        Ok(())
+    }
+}
+
+fn method_not_found(id: RequestId, method: &str) -> Response {
+    Response::new_err(
+        id,
+        lsp_server::ErrorCode::MethodNotFound as i32,
+        format!("unsupported request '{}'", method),
+    )
+}
+
+fn invalid_params(id: RequestId, method: &str, error: &serde_json::Error) -> Response {
+    Response::new_err(
+        id,
+        lsp_server::ErrorCode::InvalidParams as i32,
+        format!("invalid parameters for '{}': {}", method, error),
+    )
+}
+
+/// The parameters of a notification, or `None` after logging why they
+/// cannot be read.
+fn notification_params<P: DeserializeOwned>(method: &str, params: serde_json::Value) -> Option<P> {
+    serde_json::from_value(params)
+        .inspect_err(|error| eprintln!("ignoring '{}': invalid parameters: {}", method, error))
+        .ok()
+}
+
+/// The analyzer language of a document, from the client's language id or
+/// else the file extension.
+fn language(language_id: &str, uri: &Url) -> Option<String> {
+    let language = match language_id {
+        "javascript" | "javascriptreact" => "javascript",
+        "typescript" | "typescriptreact" => "typescript",
+        "python" | "java" | "c" | "cpp" | "rust" => language_id,
+        _ => big_o_analyser::language_for_path(Path::new(uri.path()))?,
+    };
+    Some(language.to_string())
+}
+
+/// The whole first line of `function`.
+fn header_range(document: &Document, function: &FunctionAnalysis) -> Range {
+    let line = function.line_start().saturating_sub(1);
+    let length = document
+        .text
+        .lines()
+        .nth(line)
+        .map_or(0, |text| text.encode_utf16().count());
+    Range::new(
+        Position::new(line as u32, 0),
+        Position::new(line as u32, length as u32),
+    )
+}
+
+/// A warning on every function above `max_complexity`.
+fn diagnostics(document: &Document, max_complexity: &Complexity) -> Vec<Diagnostic> {
+    document
+        .analysis
+        .functions()
+        .iter()
+        .filter(|function| function.complexity().order(max_complexity).is_gt())
+        .map(|function| Diagnostic {
+            range: header_range(document, function),
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: Some(NumberOrString::String("complexity-threshold".to_string())),
+            source: Some("big-o-analyser".to_string()),
+            message: format!(
+                "'{}' is {}, above the maximum of {}",
+                function.function(),
+                function.complexity(),
+                max_complexity
+            ),
+            ..Diagnostic::default()
+        })
+        .collect()
+}
+
+/// `O(n²) time · O(n) space` above every function.
+fn code_lenses(document: &Document) -> Vec<CodeLens> {
+    document
+        .analysis
+        .functions()
+        .iter()
+        .map(|function| CodeLens {
+            range: header_range(document, function),
+            command: Some(Command {
+                title: format!(
+                    "{} time · {} space",
+                    function.complexity(),
+                    function.space_complexity().complexity()
+                ),
+                command: String::new(),
+                arguments: None,
+            }),
+            data: None,
+        })
+        .collect()
+}
+
+/// The analysis of the innermost function around `position`.
+fn hover(document: &Document, position: Position) -> Option<Hover> {
+    let line = position.line as usize + 1;
+    let function = document
+        .analysis
+        .functions()
+        .iter()
+        .filter(|function| (function.line_start()..=function.line_end()).contains(&line))
+        .min_by_key(|function| function.line_end() - function.line_start())?;
+
+    let cases = function.cases();
+    let mut text = format!(
+        "**{}**: {} time, {} space ({:.0}% confidence)\n\nBest {}, average {}, worst {}\n",
+        function.function(),
+        function.complexity(),
+        function.space_complexity().complexity(),
+        function.confidence() * 100.0,
+        cases.best(),
+        cases.average(),
+        cases.worst()
+    );
+    for detail in function.details().iter().chain(cases.details()) {
+        text.push_str(&format!("\n- {}", detail));
+    }
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: text,
+        }),
+        range: Some(header_range(document, function)),
+    })
+}
@@ -0,0 +1,712 @@
+//! Block structure of a source file.
+//!
+//! Turns the significant tokens of a file into a tree of scopes (functions,
+//! loops, conditionals and plain blocks) with exact line spans. Brace
+//! languages are parsed statement by statement so that K&R and Allman
+//! styles, braces inside literals and loop bodies without braces all nest
+//! correctly; Python is parsed by logical line and indentation.
+
+use crate::lexer::{self, Token, TokenKind};
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockKind {
+    Root,
+    Function(String),
+    Loop,
+    Conditional,
+    Plain,
+}
+
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub kind: BlockKind,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Token range of the whole block, header included.
+    pub span: Range<usize>,
+    /// Token range of the header, e.g. `for (i = 0; i < n; i++)`.
+    pub header: Range<usize>,
+    pub children: Vec<Block>,
+}
+
+impl Block {
+    pub fn is_function(&self) -> bool {
+        matches!(self.kind, BlockKind::Function(_))
+    }
+
+    /// All function blocks of the tree in source order, nested ones included.
+    pub fn functions(&self) -> Vec<&Block> {
+        let mut functions = Vec::new();
+        self.collect_functions(&mut functions);
+        functions
+    }
+
+    fn collect_functions<'a>(&'a self, functions: &mut Vec<&'a Block>) {
+        for child in &self.children {
+            if child.is_function() {
+                functions.push(child);
+            }
+            child.collect_functions(functions);
+        }
+    }
+
+    /// Deepest loop nesting inside this block, not counting nested functions.
+    pub fn loop_depth(&self) -> usize {
+        self.children
+            .iter()
+            .filter(|child| !child.is_function())
+            .map(|child| child.nested_loop_depth())
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn nested_loop_depth(&self) -> usize {
+        let own = usize::from(self.kind == BlockKind::Loop);
+        own + self.loop_depth()
+    }
+
+    /// Loops containing the token at `index`, outermost first, without
+    /// looking into nested functions.
+    pub fn enclosing_loops(&self, index: usize) -> Vec<&Block> {
+        let mut chain = Vec::new();
+        let mut block = self;
+        while let Some(child) = block
+            .children
+            .iter()
+            .find(|child| !child.is_function() && child.span.contains(&index))
+        {
+            if child.kind == BlockKind::Loop {
+                chain.push(child);
+            }
+            block = child;
+        }
+        chain
+    }
+
+    /// Token indices belonging to this block but not to nested functions.
+    pub fn own_token_indices(&self) -> Vec<usize> {
+        let mut excluded: Vec<Range<usize>> = Vec::new();
+        self.collect_nested_function_spans(&mut excluded);
+        self.span
+            .clone()
+            .filter(|i| !excluded.iter().any(|range| range.contains(i)))
+            .collect()
+    }
+
+    fn collect_nested_function_spans(&self, spans: &mut Vec<Range<usize>>) {
+        for child in &self.children {
+            if child.is_function() {
+                spans.push(child.span.clone());
+            } else {
+                child.collect_nested_function_spans(spans);
+            }
+        }
+    }
+}
+
+/// Builds the block tree for `tokens`, which must not contain comments or
+/// preprocessor directives.
+pub fn build_block_tree(tokens: &[Token], language: &str) -> Block {
+    let language = language.to_lowercase();
+    let children = match language.as_str() {
+        "python" => IndentParser::new(tokens).parse(),
+        _ => BraceParser {
+            tokens,
+            pos: 0,
+            language,
+        }
+        .parse_sequence(false),
+    };
+
+    Block {
+        kind: BlockKind::Root,
+        start_line: 1,
+        end_line: tokens.last().map_or(1, |t| t.end_line),
+        span: 0..tokens.len(),
+        header: 0..0,
+        children,
+    }
+}
+
+fn block_lines(tokens: &[Token], span: &Range<usize>) -> (usize, usize) {
+    let start = tokens.get(span.start).map_or(1, |t| t.line);
+    let end = span
+        .end
+        .checked_sub(1)
+        .and_then(|i| tokens.get(i))
+        .map_or(start, |t| t.end_line);
+    (start, end)
+}
+
+struct BraceParser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    language: String,
+}
+
+impl BraceParser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn parse_sequence(&mut self, until_close: bool) -> Vec<Block> {
+        let mut blocks = Vec::new();
+
+        while let Some(token) = self.peek() {
+            if token.is_punct("}") {
+                if until_close {
+                    break;
+                }
+                self.pos += 1;
+                continue;
+            }
+            blocks.extend(self.parse_statement());
+        }
+
+        blocks
+    }
+
+    fn parse_statement(&mut self) -> Vec<Block> {
+        if self.is_control_keyword(self.pos) {
+            return vec![self.parse_control()];
+        }
+        self.parse_simple_statement()
+    }
+
+    fn is_control_keyword(&self, index: usize) -> bool {
+        let Some(token) = self.tokens.get(index) else {
+            return false;
+        };
+        if token.kind != TokenKind::Keyword {
+            return false;
+        }
+        let is_control = match token.text.as_str() {
+            "for" | "while" | "do" | "if" | "else" | "switch" | "try" | "catch" | "finally" => true,
+            "loop" | "match" | "unsafe" => self.language == "rust",
+            _ => false,
+        };
+        if !is_control {
+            return false;
+        }
+
+        // `promise.catch(...)`, `impl Trait for Type`, `for<'a> Fn(&'a T)`
+        let previous = index.checked_sub(1).map(|i| &self.tokens[i]);
+        let after_dot = previous.is_some_and(|t| t.is_punct("."));
+        let after_type = token.text == "for"
+            && previous.is_some_and(|t| t.kind == TokenKind::Identifier || t.is_punct(">"));
+        let higher_ranked =
+            token.text == "for" && self.tokens.get(index + 1).is_some_and(|t| t.is_punct("<"));
+        !(after_dot || after_type || higher_ranked)
+    }
+
+    fn parse_simple_statement(&mut self) -> Vec<Block> {
+        let start = self.pos;
+        let mut blocks = Vec::new();
+        let mut depth = 0usize;
+        // Start of the expression segment that precedes an embedded `{`
+        let mut segment_start = start;
+        let mut segment_stack: Vec<usize> = Vec::new();
+
+        while let Some(token) = self.peek() {
+            if self.pos > start && self.is_control_keyword(self.pos) {
+                let block = self.parse_control();
+                let ended_with_brace = self.tokens[block.span.end - 1].is_punct("}");
+                blocks.push(block);
+                if depth == 0 && ended_with_brace && !self.statement_continues() {
+                    break;
+                }
+                continue;
+            }
+
+            if token.kind != TokenKind::Punctuation {
+                self.pos += 1;
+                continue;
+            }
+
+            match token.text.as_str() {
+                "(" | "[" => {
+                    depth += 1;
+                    segment_stack.push(segment_start);
+                    self.pos += 1;
+                    segment_start = self.pos;
+                    continue;
+                }
+                ")" | "]" => {
+                    depth = depth.saturating_sub(1);
+                    segment_start = segment_stack.pop().unwrap_or(start);
+                }
+                "," if depth > 0 => {
+                    self.pos += 1;
+                    segment_start = self.pos;
+                    continue;
+                }
+                ";" if depth == 0 => {
+                    self.pos += 1;
+                    break;
+                }
+                "}" => break,
+                "{" => {
+                    let kind = self.classify_header(segment_start, self.pos);
+                    let header = segment_start..self.pos;
+                    blocks.push(self.parse_braced(kind, header));
+                    if depth == 0 && !self.statement_continues() {
+                        break;
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            self.pos += 1;
+        }
+
+        blocks
+    }
+
+    /// Decides, after a closing brace at statement level, whether the
+    /// statement goes on (`});`, `}.bind(this)`, `} = value`) or has ended.
+    fn statement_continues(&mut self) -> bool {
+        let Some(next) = self.peek() else {
+            return false;
+        };
+        if next.is_punct(";") {
+            self.pos += 1;
+            return false;
+        }
+        let previous_line = self.tokens[self.pos - 1].line;
+        next.kind == TokenKind::Punctuation
+            && next.line == previous_line
+            && !next.is_punct("{")
+            && !next.is_punct("}")
+    }
+
+    fn parse_braced(&mut self, kind: BlockKind, header: Range<usize>) -> Block {
+        let open = self.pos;
+        self.pos += 1;
+        let children = self.parse_sequence(true);
+        if self.peek().is_some() {
+            self.pos += 1;
+        }
+        let span = header.start.min(open)..self.pos;
+        let (mut start_line, end_line) = block_lines(self.tokens, &span);
+        // Attributes and annotations before a definition are not part of it
+        if let BlockKind::Function(name) = &kind
+            && let Some(token) = self.tokens[header.clone()]
+                .iter()
+                .find(|t| t.is_ident(name))
+        {
+            start_line = token.line;
+        }
+
+        Block {
+            kind,
+            start_line,
+            end_line,
+            span,
+            header,
+            children,
+        }
+    }
+
+    /// Parses the body of a control statement: a braced block or a single
+    /// statement.
+    fn parse_body(&mut self) -> Vec<Block> {
+        match self.peek() {
+            Some(token) if token.is_punct("{") => {
+                self.pos += 1;
+                let children = self.parse_sequence(true);
+                if self.peek().is_some() {
+                    self.pos += 1;
+                }
+                children
+            }
+            Some(_) => self.parse_statement(),
+            None => Vec::new(),
+        }
+    }
+
+    fn parse_control(&mut self) -> Block {
+        let start = self.pos;
+        let keyword = self.tokens[start].text.clone();
+        self.pos += 1;
+
+        let (kind, header, children) = match keyword.as_str() {
+            "do" => {
+                let children = self.parse_body();
+                let header_start = self.pos;
+                if self.peek().is_some_and(|t| t.is_keyword("while")) {
+                    self.pos += 1;
+                    self.skip_condition();
+                    if self.peek().is_some_and(|t| t.is_punct(";")) {
+                        self.pos += 1;
+                    }
+                }
+                (BlockKind::Loop, header_start..self.pos, children)
+            }
+            "else" if self.peek().is_some_and(|t| t.is_keyword("if")) => {
+                let mut block = self.parse_control();
+                block.span.start = start;
+                block.start_line = self.tokens[start].line;
+                return block;
+            }
+            _ => {
+                let header_start = self.pos;
+                if self.takes_condition(&keyword) {
+                    self.skip_condition();
+                }
+                let header = header_start..self.pos;
+                let children = self.parse_body();
+                let kind = match keyword.as_str() {
+                    "for" | "while" | "loop" => BlockKind::Loop,
+                    "if" | "else" | "switch" | "match" => BlockKind::Conditional,
+                    _ => BlockKind::Plain,
+                };
+                (kind, header, children)
+            }
+        };
+
+        let span = start..self.pos;
+        let (start_line, end_line) = block_lines(self.tokens, &span);
+        Block {
+            kind,
+            start_line,
+            end_line,
+            span,
+            header,
+            children,
+        }
+    }
+
+    fn takes_condition(&self, keyword: &str) -> bool {
+        match keyword {
+            "else" | "finally" | "loop" | "unsafe" => false,
+            // Java try-with-resources
+            "try" => self.peek().is_some_and(|t| t.is_punct("(")),
+            // Optional `catch` binding in JavaScript
+            "catch" => self.peek().is_some_and(|t| t.is_punct("(")),
+            _ => true,
+        }
+    }
+
+    /// Skips a loop or branch condition: a parenthesized group, or for
+    /// languages without parentheses, everything up to the opening brace.
+    fn skip_condition(&mut self) {
+        // `for await (...)`, `if constexpr (...)`
+        if self.peek().is_some_and(|t| {
+            t.is_keyword("await") || t.is_ident("await") || t.is_ident("constexpr")
+        }) {
+            self.pos += 1;
+        }
+
+        if self.peek().is_some_and(|t| t.is_punct("(")) && self.language != "rust" {
+            self.pos = lexer::matching_close(self.tokens, self.pos)
+                .map_or(self.tokens.len(), |close| close + 1);
+            return;
+        }
+
+        let mut depth = 0usize;
+        while let Some(token) = self.peek() {
+            match token.text.as_str() {
+                "(" | "[" if token.kind == TokenKind::Punctuation => depth += 1,
+                ")" | "]" if token.kind == TokenKind::Punctuation => {
+                    depth = depth.saturating_sub(1)
+                }
+                "{" | ";" | "}" if token.kind == TokenKind::Punctuation && depth == 0 => return,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn classify_header(&self, start: usize, end: usize) -> BlockKind {
+        let header = &self.tokens[start..end];
+        let Some(last) = header.last() else {
+            return BlockKind::Plain;
+        };
+
+        // Type bodies (`struct` and `enum` may also start a C return type)
+        let scope_keywords = ["class", "interface", "namespace", "impl", "trait", "mod"];
+        if header
+            .iter()
+            .take_while(|t| !t.is_punct("("))
+            .any(|t| t.kind == TokenKind::Keyword && scope_keywords.contains(&t.text.as_str()))
+        {
+            return BlockKind::Plain;
+        }
+
+        // Rust `fn name<T>(...) -> R where ... {`
+        if let Some(fn_pos) = header.iter().position(|t| t.is_keyword("fn")) {
+            return match header.get(fn_pos + 1) {
+                Some(name) if name.kind == TokenKind::Identifier => {
+                    BlockKind::Function(name.text.clone())
+                }
+                _ => BlockKind::Plain,
+            };
+        }
+
+        // JavaScript `function name(...)` and `name = function (...)`
+        if let Some(fn_pos) = header.iter().position(|t| t.is_keyword("function")) {
+            let declared = header[fn_pos + 1..]
+                .iter()
+                .find(|t| !t.is_punct("*"))
+                .filter(|t| t.kind == TokenKind::Identifier);
+            if let Some(name) = declared {
+                return BlockKind::Function(name.text.clone());
+            }
+            return self
+                .assigned_name(&header[..fn_pos])
+                .unwrap_or(BlockKind::Plain);
+        }
+
+        // Arrow functions bound to a name
+        if last.is_punct("=>") {
+            return self.assigned_name(header).unwrap_or(BlockKind::Plain);
+        }
+
+        // C-style definitions and methods: `type name(params) qualifiers {`
+        let Some(paren) = header.iter().position(|t| t.is_punct("(")) else {
+            return BlockKind::Plain;
+        };
+        // Generic methods put type parameters between the name and `(`
+        let Some(name_pos) = paren
+            .checked_sub(1)
+            .and_then(|i| skip_type_parameters(header, i))
+        else {
+            return BlockKind::Plain;
+        };
+        let name = &header[name_pos];
+        let prefix_is_declaration = header[..name_pos].iter().all(|t| {
+            !(t.is_punct("=")
+                || t.is_punct(".")
+                || t.is_punct("=>")
+                || t.is_punct("(")
+                || t.is_keyword("new")
+                || t.is_keyword("return"))
+        });
+        if name.kind == TokenKind::Identifier && prefix_is_declaration {
+            BlockKind::Function(name.text.clone())
+        } else {
+            BlockKind::Plain
+        }
+    }
+
+    /// Name from `const name = ...`, `name = ...` or `name: ...`.
+    fn assigned_name(&self, header: &[Token]) -> Option<BlockKind> {
+        let mut iter = header.iter().skip_while(|t| {
+            t.kind == TokenKind::Keyword || t.is_ident("async") || t.is_ident("static")
+        });
+        let name = iter.next().filter(|t| t.kind == TokenKind::Identifier)?;
+        let mut next = iter.next()?;
+        // TypeScript annotation: `const name: Type = (...) => {`
+        if next.is_punct(":") && header.iter().any(|t| t.is_punct("=")) {
+            next = iter.find(|t| t.is_punct("="))?;
+        }
+        (next.is_punct("=") || next.is_punct(":")).then(|| BlockKind::Function(name.text.clone()))
+    }
+}
+
+/// A Python logical line: physical lines joined by brackets or backslashes.
+struct LogicalLine {
+    tokens: Range<usize>,
+    indent: usize,
+}
+
+struct IndentParser<'a> {
+    tokens: &'a [Token],
+    lines: Vec<LogicalLine>,
+}
+
+impl<'a> IndentParser<'a> {
+    fn new(tokens: &'a [Token]) -> Self {
+        let mut lines = Vec::new();
+        let mut start = 0usize;
+        let mut depth = 0usize;
+
+        for (i, token) in tokens.iter().enumerate() {
+            if token.kind == TokenKind::Punctuation {
+                match token.text.as_str() {
+                    "(" | "[" | "{" => depth += 1,
+                    ")" | "]" | "}" => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+            }
+
+            let ends_line = tokens.get(i + 1).is_none_or(|next| {
+                depth == 0 && next.line > token.end_line && !token.is_punct("\\")
+            });
+
+            if ends_line {
+                let indent = tokens[start].column;
+                lines.push(LogicalLine {
+                    tokens: start..i + 1,
+                    indent,
+                });
+                start = i + 1;
+            }
+        }
+
+        Self { tokens, lines }
+    }
+
+    fn parse(&self) -> Vec<Block> {
+        let (blocks, _) = self.parse_suite(0, 0);
+        blocks
+    }
+
+    /// Parses consecutive logical lines indented deeper than `parent_indent`.
+    fn parse_suite(&self, mut index: usize, parent_indent: usize) -> (Vec<Block>, usize) {
+        let mut blocks = Vec::new();
+
+        while let Some(line) = self.lines.get(index) {
+            if line.indent <= parent_indent {
+                break;
+            }
+
+            let first = &self.tokens[line.tokens.start];
+            if first.is_punct("@") {
+                // Decorators belong to the header of the following definition
+                let mut def_index = index;
+                while self
+                    .lines
+                    .get(def_index)
+                    .is_some_and(|l| self.tokens[l.tokens.start].is_punct("@"))
+                {
+                    def_index += 1;
+                }
+                if def_index < self.lines.len() && self.compound_keyword(def_index).is_some() {
+                    let header_start = line.tokens.start;
+                    let (block, next) = self.parse_compound(def_index, header_start);
+                    blocks.push(block);
+                    index = next;
+                } else {
+                    index = def_index;
+                }
+            } else if self.compound_keyword(index).is_some() {
+                let (block, next) = self.parse_compound(index, line.tokens.start);
+                blocks.push(block);
+                index = next;
+            } else {
+                index += 1;
+            }
+        }
+
+        (blocks, index)
+    }
+
+    /// Index of the keyword opening a compound statement on `line`, if any.
+    fn compound_keyword(&self, line: usize) -> Option<usize> {
+        let range = &self.lines[line].tokens;
+        let mut index = range.start;
+        if self.tokens[index].is_keyword("async") {
+            index += 1;
+        }
+        let token = self.tokens.get(index).filter(|_| index < range.end)?;
+        let is_compound = token.kind == TokenKind::Keyword
+            && matches!(
+                token.text.as_str(),
+                "def"
+                    | "class"
+                    | "for"
+                    | "while"
+                    | "if"
+                    | "elif"
+                    | "else"
+                    | "try"
+                    | "except"
+                    | "finally"
+                    | "with"
+            );
+        is_compound.then_some(index)
+    }
+
+    fn parse_compound(&self, index: usize, header_start: usize) -> (Block, usize) {
+        let line = &self.lines[index];
+        let keyword_index = self.compound_keyword(index).unwrap_or(line.tokens.start);
+        let keyword = &self.tokens[keyword_index];
+
+        let kind = match keyword.text.as_str() {
+            "def" => match self.tokens.get(keyword_index + 1) {
+                Some(name) if name.kind == TokenKind::Identifier => {
+                    BlockKind::Function(name.text.clone())
+                }
+                _ => BlockKind::Plain,
+            },
+            "for" | "while" => BlockKind::Loop,
+            "if" | "elif" | "else" => BlockKind::Conditional,
+            _ => BlockKind::Plain,
+        };
+
+        // The header ends at the first `:` outside brackets and lambdas
+        let mut depth = 0usize;
+        let mut lambdas = 0usize;
+        let mut colon = line.tokens.end;
+        for i in keyword_index + 1..line.tokens.end {
+            let token = &self.tokens[i];
+            match token.text.as_str() {
+                "(" | "[" | "{" if token.kind == TokenKind::Punctuation => depth += 1,
+                ")" | "]" | "}" if token.kind == TokenKind::Punctuation => {
+                    depth = depth.saturating_sub(1)
+                }
+                "lambda" if token.kind == TokenKind::Keyword => lambdas += 1,
+                ":" if token.kind == TokenKind::Punctuation && depth == 0 => {
+                    if lambdas == 0 {
+                        colon = i;
+                        break;
+                    }
+                    lambdas -= 1;
+                }
+                _ => {}
+            }
+        }
+
+        let header = header_start..colon.min(line.tokens.end);
+        let (body_end, children, next) = if colon + 1 < line.tokens.end {
+            // Single-line body: `for x in xs: total += x`
+            (line.tokens.end, Vec::new(), index + 1)
+        } else {
+            let (children, next) = self.parse_suite(index + 1, line.indent);
+            let body_end = self
+                .lines
+                .get(next.saturating_sub(1))
+                .filter(|_| next > index + 1)
+                .map_or(line.tokens.end, |l| l.tokens.end);
+            (body_end, children, next)
+        };
+
+        let span = header_start..body_end;
+        let start_line = keyword.line;
+        let (_, end_line) = block_lines(self.tokens, &span);
+
+        (
+            Block {
+                kind,
+                start_line,
+                end_line,
+                span,
+                header,
+                children,
+            },
+            next,
+        )
+    }
+}
+
+/// The index of the token before the `<...>` ending at `end`, or `end`
+/// itself when it does not close type parameters.
+fn skip_type_parameters(tokens: &[Token], end: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for i in (0..=end).rev() {
+        let token = &tokens[i];
+        if token.is_punct(">") || token.is_punct(">>") {
+            depth += token.text.len();
+        } else if token.is_punct("<") {
+            depth = depth.checked_sub(1)?;
+        } else if i == end {
+            return Some(end);
+        }
+        if depth == 0 {
+            return i.checked_sub(1);
+        }
+    }
+    None
+}
@@ -0,0 +1,214 @@
+//! Complexity budgets.
+//!
+//! A budget bounds the time complexity of every function: a global maximum,
+//! overridden for the files under a path and for single functions. Checking
+//! an analysis against it lists every function over its bound, so that a
+//! build can fail on them.
+//!
+//! Budgets read from JSON:
+//!
+//! ```json
+//! {
+//!   "max": "O(n log n)",
+//!   "paths": { "src/legacy/": "O(n^2)" },
+//!   "functions": { "fib": "O(2^n)", "src/solver.py:solve": "O(n^3)" }
+//! }
+//! ```
+
+use crate::AnalysisResult;
+use crate::complexity::Complexity;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Budget {
+    /// Bound of every function without an override.
+    max: Complexity,
+    /// Bounds for the files under a path; the longest matching path wins.
+    #[serde(default)]
+    paths: BTreeMap<String, Complexity>,
+    /// Bounds for functions, by name or by `path:name`. These win over
+    /// path bounds, and `path:name` over a bare name.
+    #[serde(default)]
+    functions: BTreeMap<String, Complexity>,
+}
+
+/// A function over its bound.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Violation {
+    path: String,
+    function: String,
+    line_start: usize,
+    line_end: usize,
+    complexity: Complexity,
+    budget: Complexity,
+    /// Where the bound comes from: `global maximum`, `path 'src/'` or
+    /// `function 'fib'`.
+    scope: String,
+}
+
+/// Outcome of checking an analysis against a budget.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetReport {
+    /// Number of functions checked.
+    checked: usize,
+    violations: Vec<Violation>,
+}
+
+impl Budget {
+    /// A budget bounding every function by `max`.
+    pub fn new(max: Complexity) -> Self {
+        Self {
+            max,
+            paths: BTreeMap::new(),
+            functions: BTreeMap::new(),
+        }
+    }
+
+    /// Bounds the functions of the files under `path` by `max`.
+    pub fn with_path(mut self, path: &str, max: Complexity) -> Self {
+        self.paths.insert(path.to_string(), max);
+        self
+    }
+
+    /// Bounds the functions named `function`, or `path:name`, by `max`.
+    pub fn with_function(mut self, function: &str, max: Complexity) -> Self {
+        self.functions.insert(function.to_string(), max);
+        self
+    }
+
+    pub fn max(&self) -> &Complexity {
+        &self.max
+    }
+
+    /// Replaces the global maximum, keeping the overrides.
+    pub fn set_max(&mut self, max: Complexity) {
+        self.max = max;
+    }
+
+    /// The bound of `function` in the file at `path`, and where it comes
+    /// from.
+    pub fn limit(&self, path: &str, function: &str) -> (&Complexity, String) {
+        let qualified = self.functions.iter().find(|(key, _)| {
+            key.rsplit_once(':')
+                .is_some_and(|(prefix, name)| name == function && under(path, prefix))
+        });
+        if let Some((key, max)) = qualified {
+            return (max, format!("function '{}'", key));
+        }
+        if let Some(max) = self.functions.get(function) {
+            return (max, format!("function '{}'", function));
+        }
+        let directory = self
+            .paths
+            .iter()
+            .filter(|(prefix, _)| under(path, prefix))
+            .max_by_key(|(prefix, _)| prefix.len());
+        if let Some((prefix, max)) = directory {
+            return (max, format!("path '{}'", prefix));
+        }
+        (&self.max, "global maximum".to_string())
+    }
+
+    /// Checks every function of `files`, each given with its path.
+    pub fn evaluate(&self, files: &[(&str, &AnalysisResult)]) -> BudgetReport {
+        let mut checked = 0;
+        let mut violations = Vec::new();
+        for &(path, result) in files {
+            for function in result.functions() {
+                checked += 1;
+                let (budget, scope) = self.limit(path, function.function());
+                if function.complexity().order(budget).is_gt() {
+                    violations.push(Violation {
+                        path: path.to_string(),
+                        function: function.function().to_string(),
+                        line_start: function.line_start(),
+                        line_end: function.line_end(),
+                        complexity: function.complexity().clone(),
+                        budget: budget.clone(),
+                        scope,
+                    });
+                }
+            }
+        }
+        BudgetReport {
+            checked,
+            violations,
+        }
+    }
+}
+
+impl Violation {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn function(&self) -> &str {
+        &self.function
+    }
+
+    pub fn line_start(&self) -> usize {
+        self.line_start
+    }
+
+    pub fn line_end(&self) -> usize {
+        self.line_end
+    }
+
+    pub fn complexity(&self) -> &Complexity {
+        &self.complexity
+    }
+
+    pub fn budget(&self) -> &Complexity {
+        &self.budget
+    }
+
+    pub fn scope(&self) -> &str {
+        &self.scope
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: '{}' is {}, over its budget of {} ({})",
+            self.path, self.line_start, self.function, self.complexity, self.budget, self.scope
+        )
+    }
+}
+
+impl BudgetReport {
+    pub fn checked(&self) -> usize {
+        self.checked
+    }
+
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
+    /// Whether every function is within its bound.
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Whether `path` is `prefix` or lies below it, comparing whole components
+/// with either slash.
+fn under(path: &str, prefix: &str) -> bool {
+    let (path, prefix) = (normalize(path), normalize(prefix));
+    prefix.is_empty()
+        || path == prefix
+        || path
+            .strip_prefix(&prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// `path` with forward slashes and no leading `./` or trailing slash.
+pub(crate) fn normalize(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    path.trim_start_matches("./")
+        .trim_end_matches('/')
+        .to_string()
+}
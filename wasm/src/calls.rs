@@ -0,0 +1,376 @@
+//! Call graph of the functions in one file.
+//!
+//! Calls between user functions are not free: a helper that walks its
+//! argument costs a walk every time it is called. Callees are analyzed
+//! before their callers so that each call can be charged the callee's
+//! complexity, rewritten in the caller's variables and multiplied by the
+//! loops around the call.
+
+use crate::FunctionInfo;
+use crate::blocks::Block;
+use crate::complexity::Complexity;
+use crate::cost;
+use crate::lexer::{self, Token, TokenKind};
+use crate::loops::{self, SizeSource, Symbols};
+use std::collections::HashMap;
+
+/// What a caller needs to know about an analyzed function.
+#[derive(Debug, Clone)]
+pub struct Summary {
+    pub complexity: Complexity,
+    pub parameters: Vec<String>,
+    /// What each variable of `complexity` measures, in the callee's terms.
+    pub sources: Vec<(String, SizeSource)>,
+    /// The function followed by the callees its most expensive call goes
+    /// through.
+    pub chain: Vec<String>,
+}
+
+/// A call from one function of the file to another.
+#[derive(Debug, Clone)]
+pub struct CallSite {
+    /// Position of the callee in the node list.
+    pub callee: usize,
+    /// Token index of the callee's name.
+    pub index: usize,
+}
+
+/// A function placed in the call graph, with the file defining it.
+pub struct Node<'a> {
+    /// Position of the file in the module list.
+    pub file: usize,
+    /// Tokens of that file.
+    pub tokens: &'a [Token],
+    pub function: &'a FunctionInfo,
+}
+
+/// A file seen from the call graph.
+#[derive(Debug, Clone, Default)]
+pub struct Module {
+    /// Name calls qualify the file's functions with: `utils` in
+    /// `utils.helper(x)`.
+    pub name: String,
+    /// Files whose functions this file can call.
+    pub imports: Vec<usize>,
+}
+
+/// Which functions call which, by position in the node list.
+#[derive(Debug, Default)]
+pub struct CallGraph {
+    sites: Vec<Vec<CallSite>>,
+}
+
+impl CallGraph {
+    /// Finds the calls each node makes to the others: plain calls to the
+    /// same file or a file it imports, and calls qualified with the name of
+    /// an imported file. Calls to itself are left to the recursion analysis.
+    pub fn build(nodes: &[Node], modules: &[Module]) -> Self {
+        let mut by_name: HashMap<(usize, &str), usize> = HashMap::new();
+        for (position, node) in nodes.iter().enumerate() {
+            by_name
+                .entry((node.file, node.function.name.as_str()))
+                .or_insert(position);
+        }
+        let resolve = |file: usize, name: &str, qualifier: Option<&str>| {
+            let imported = modules[file].imports.iter().filter(|&&imported| {
+                qualifier.is_none_or(|q| modules[imported].name.eq_ignore_ascii_case(q))
+            });
+            qualifier
+                .is_none()
+                .then_some(file)
+                .into_iter()
+                .chain(imported.copied())
+                .find_map(|file| by_name.get(&(file, name)).copied())
+        };
+
+        let sites = nodes
+            .iter()
+            .enumerate()
+            .map(|(position, caller)| {
+                let tokens = caller.tokens;
+                let block = &caller.function.block;
+                block
+                    .own_token_indices()
+                    .into_iter()
+                    .filter(|i| !block.header.contains(i))
+                    .filter_map(|i| {
+                        let qualifier = call_qualifier(tokens, i)?;
+                        let callee = resolve(caller.file, &tokens[i].text, qualifier)?;
+                        (callee != position).then_some(CallSite { callee, index: i })
+                    })
+                    .collect()
+            })
+            .collect();
+        Self { sites }
+    }
+
+    pub fn sites(&self, caller: usize) -> &[CallSite] {
+        &self.sites[caller]
+    }
+
+    /// Groups of functions that call each other, every group after the
+    /// groups it calls into (Tarjan's algorithm). A function outside any
+    /// cycle forms a group of its own.
+    pub fn components(&self) -> Vec<Vec<usize>> {
+        let mut search = Tarjan {
+            graph: self,
+            next: 0,
+            order: vec![None; self.sites.len()],
+            low: vec![0; self.sites.len()],
+            stack: Vec::new(),
+            on_stack: vec![false; self.sites.len()],
+            components: Vec::new(),
+        };
+        for function in 0..self.sites.len() {
+            if search.order[function].is_none() {
+                search.visit(function);
+            }
+        }
+        search.components
+    }
+}
+
+/// State of one strongly connected components search.
+struct Tarjan<'a> {
+    graph: &'a CallGraph,
+    next: usize,
+    /// Visiting order of each function, once visited.
+    order: Vec<Option<usize>>,
+    /// Earliest function on the stack reachable from each function.
+    low: Vec<usize>,
+    stack: Vec<usize>,
+    on_stack: Vec<bool>,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+    /// Visits `root` and every function it reaches, depth first. The path
+    /// from `root` is kept on an explicit stack, with the next call site of
+    /// each function, so that long call chains cannot overflow the stack.
+    fn visit(&mut self, root: usize) {
+        let graph = self.graph;
+        self.enter(root);
+        let mut path = vec![(root, 0)];
+        while let Some((function, next_site)) = path.last_mut() {
+            let function = *function;
+            if let Some(site) = graph.sites[function].get(*next_site) {
+                *next_site += 1;
+                match self.order[site.callee] {
+                    None => {
+                        self.enter(site.callee);
+                        path.push((site.callee, 0));
+                    }
+                    Some(order) if self.on_stack[site.callee] => {
+                        self.low[function] = self.low[function].min(order);
+                    }
+                    Some(_) => {}
+                }
+                continue;
+            }
+
+            path.pop();
+            if let Some(&(caller, _)) = path.last() {
+                self.low[caller] = self.low[caller].min(self.low[function]);
+            }
+            if Some(self.low[function]) == self.order[function] {
+                let mut component = Vec::new();
+                while let Some(member) = self.stack.pop() {
+                    self.on_stack[member] = false;
+                    component.push(member);
+                    if member == function {
+                        break;
+                    }
+                }
+                component.sort_unstable();
+                self.components.push(component);
+            }
+        }
+    }
+
+    fn enter(&mut self, function: usize) {
+        self.order[function] = Some(self.next);
+        self.low[function] = self.next;
+        self.next += 1;
+        self.stack.push(function);
+        self.on_stack[function] = true;
+    }
+}
+
+/// For a call at `index`, the file name qualifying it: `Some(None)` for
+/// `helper(x)` and `self.helper(x)`, `Some(Some("utils"))` for
+/// `utils.helper(x)`, and `None` for anything that is not a call, such as
+/// `new Helper()`.
+fn call_qualifier(tokens: &[Token], index: usize) -> Option<Option<&str>> {
+    if !lexer::is_call(tokens, index) {
+        return None;
+    }
+    match index.checked_sub(1).map(|i| &tokens[i]) {
+        Some(previous) if previous.is_punct(".") => {
+            let receiver = &tokens[index.checked_sub(2)?];
+            match receiver.text.as_str() {
+                "self" | "this" => Some(None),
+                _ if receiver.kind == TokenKind::Identifier => Some(Some(&receiver.text)),
+                _ => None,
+            }
+        }
+        Some(previous) if previous.is_keyword("new") => None,
+        _ => Some(None),
+    }
+}
+
+/// What the calls of one function cost in total.
+#[derive(Debug)]
+pub struct CallCost {
+    pub complexity: Complexity,
+    pub details: Vec<String>,
+    /// Chain of the most expensive call, starting with the caller.
+    pub chain: Vec<String>,
+}
+
+/// Charges every call in `sites` the complexity of its callee, in the
+/// variables of `function`, times the iterations of the loops around it.
+/// Callees without a summary, because they call back into `function`, are
+/// left to the recursion analysis.
+pub fn call_cost(
+    tokens: &[Token],
+    name: &str,
+    function: &Block,
+    sites: &[CallSite],
+    summaries: &[Option<Summary>],
+    symbols: &mut Symbols,
+) -> CallCost {
+    let mut total = CallCost {
+        complexity: Complexity::constant(),
+        details: Vec::new(),
+        chain: vec![name.to_string()],
+    };
+    let mut dominant = Complexity::constant();
+    let signature = &tokens[function.span.start..function.header.end.max(function.span.start)];
+    let parameters = loops::parameters(signature);
+
+    for site in sites {
+        let Some(summary) = &summaries[site.callee] else {
+            continue;
+        };
+        if summary.complexity.is_constant() {
+            continue;
+        }
+        let chain = function.enclosing_loops(site.index);
+        let iterations = cost::loop_iterations(tokens, &chain, symbols);
+        let per_call = translate(
+            tokens,
+            site.index,
+            summary,
+            &parameters,
+            &chain,
+            &iterations,
+            symbols,
+        );
+        let repeated = iterations
+            .iter()
+            .fold(Complexity::constant(), |product, loop_cost| {
+                product.times(loop_cost)
+            });
+        let cost = repeated.times(&per_call);
+
+        let callee = &summary.chain[0];
+        let line = tokens[site.index].line;
+        total.details.push(if repeated.is_constant() {
+            format!("Call to '{}' at line {} costs {}", callee, line, per_call)
+        } else {
+            format!(
+                "Call to '{}' at line {} costs {} per call, {} with the loops around it",
+                callee, line, per_call, cost
+            )
+        });
+        if cost > dominant {
+            dominant = cost.clone();
+            total.chain = std::iter::once(name.to_string())
+                .chain(summary.chain.iter().cloned())
+                .collect();
+        }
+        total.complexity = total.complexity.plus(&cost);
+    }
+
+    if total.chain.len() > 1 {
+        total
+            .details
+            .push(format!("Call chain: {}", total.chain.join(" → ")));
+    }
+    total
+}
+
+/// The callee's complexity with each of its variables replaced by the
+/// caller's variable for the matching argument. An argument that is the
+/// index or element of an enclosing loop takes that loop's bound, and one
+/// measuring a caller parameter, directly or through an alias, that
+/// parameter's variable. A literal or a local holding a constant, as in
+/// `helper([1, 2, 3])`, drops the variable. Anything else, such as a local
+/// the caller built, falls back to the caller's primary variable.
+fn translate(
+    tokens: &[Token],
+    index: usize,
+    summary: &Summary,
+    parameters: &[String],
+    chain: &[&Block],
+    iterations: &[Complexity],
+    symbols: &mut Symbols,
+) -> Complexity {
+    let arguments = loops::call_arguments(tokens, index + 1);
+    let offset = loops::argument_offset(&summary.parameters, &arguments);
+
+    let mut renamed = summary.complexity.clone();
+    let mut targets = Vec::new();
+    for (position, variable) in summary.complexity.variables().iter().enumerate() {
+        let argument = summary
+            .sources
+            .iter()
+            .find(|(symbol, _)| symbol == variable)
+            .and_then(|(_, source)| {
+                let root = source.name().split(['[', '.']).next()?;
+                let parameter = summary.parameters.iter().position(|p| p == root)?;
+                let argument = arguments.get(parameter.checked_sub(offset)?)?;
+                Some((source, *argument))
+            });
+        if argument.is_some_and(|(_, argument)| symbols.is_constant(argument)) {
+            renamed = renamed.without(variable);
+            continue;
+        }
+
+        let target = argument.and_then(|(source, argument)| {
+            if let [single] = argument {
+                let bound = chain
+                    .iter()
+                    .zip(iterations)
+                    .rev()
+                    .find_map(|(block, cost)| {
+                        (loops::loop_index(tokens, block).as_deref() == Some(single.text.as_str()))
+                            .then(|| cost.variables().into_iter().next())
+                            .flatten()
+                    });
+                if bound.is_some() {
+                    return bound;
+                }
+            }
+            let source = match source {
+                SizeSource::Length(_) => loops::iterable_source(argument),
+                SizeSource::Value(_) => loops::value_source(argument),
+            }?;
+            let resolved = symbols.resolve(&source);
+            let root = resolved.name().split(['[', '.']).next()?;
+            parameters
+                .iter()
+                .any(|parameter| parameter == root)
+                .then(|| symbols.symbol_for(&source))
+        });
+
+        // Through a placeholder, so `n -> m` and `m -> n` do not collide
+        let placeholder = format!("#{}", position);
+        renamed = renamed.rename(variable, &placeholder);
+        targets.push((placeholder, target.unwrap_or_else(|| symbols.primary())));
+    }
+    for (placeholder, target) in targets {
+        renamed = renamed.rename(&placeholder, &target);
+    }
+    renamed
+}
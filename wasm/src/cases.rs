@@ -0,0 +1,305 @@
+//! Best, average and worst cases.
+//!
+//! The headline bound of a function is its typical running time. A search
+//! that can stop at the first element, a pivot that can split the input
+//! unevenly, and hash lookups that can collide all move the best or worst
+//! case away from it.
+
+use crate::blocks::Block;
+use crate::complexity::Complexity;
+use crate::cost::{self, CostNode};
+use crate::lexer::{self, Token, TokenKind};
+use crate::loops::Symbols;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Identifiers that mark a pivot-based split, as in quicksort.
+const PIVOT_NAMES: &[&str] = &["pivot", "partition"];
+
+/// Constructors and types of hash-based collections.
+const HASH_TYPES: &[&str] = &[
+    "dict",
+    "set",
+    "defaultdict",
+    "Counter",
+    "OrderedDict",
+    "frozenset",
+    "Map",
+    "Set",
+    "HashMap",
+    "HashSet",
+    "LinkedHashMap",
+    "LinkedHashSet",
+    "Hashtable",
+    "unordered_map",
+    "unordered_set",
+    "unordered_multimap",
+    "unordered_multiset",
+];
+
+/// Methods that look up or insert a key.
+const HASH_METHODS: &[&str] = &[
+    "get",
+    "has",
+    "add",
+    "set",
+    "put",
+    "insert",
+    "remove",
+    "delete",
+    "contains",
+    "containsKey",
+    "contains_key",
+    "count",
+    "find",
+    "getOrDefault",
+    "entry",
+];
+
+/// Running time of a function in its best, average and worst case.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cases {
+    best: Complexity,
+    average: Complexity,
+    worst: Complexity,
+    /// Why the cases differ.
+    details: Vec<String>,
+}
+
+impl Cases {
+    /// All three cases at `typical`, the bound reported for the function.
+    pub(crate) fn new(typical: &Complexity) -> Self {
+        Self {
+            best: typical.clone(),
+            average: typical.clone(),
+            worst: typical.clone(),
+            details: Vec::new(),
+        }
+    }
+
+    /// Variables appearing in any of the cases.
+    pub(crate) fn variables(&self) -> BTreeSet<String> {
+        let mut variables = self.best.variables();
+        variables.extend(self.worst.variables());
+        variables
+    }
+
+    pub fn best(&self) -> &Complexity {
+        &self.best
+    }
+
+    pub fn average(&self) -> &Complexity {
+        &self.average
+    }
+
+    pub fn worst(&self) -> &Complexity {
+        &self.worst
+    }
+
+    /// Why the cases differ.
+    pub fn details(&self) -> &[String] {
+        &self.details
+    }
+
+    /// Lowers the best case to `best`.
+    pub(crate) fn lower_best(&mut self, best: Complexity, reason: String) {
+        if best < self.best {
+            self.best = best;
+            self.details.push(reason);
+        }
+    }
+
+    /// Raises the worst case to `worst`.
+    pub(crate) fn raise_worst(&mut self, worst: Complexity, reason: String) {
+        if worst > self.worst {
+            self.worst = worst;
+            self.details.push(reason);
+        }
+    }
+}
+
+/// Loops a `return` or `break` can leave early, by their first line: for
+/// `return` every loop around it, for `break` the innermost one.
+pub fn early_exits(tokens: &[Token], function: &Block) -> Vec<usize> {
+    let mut lines = Vec::new();
+    for i in function.own_token_indices() {
+        let token = &tokens[i];
+        let chain = function.enclosing_loops(i);
+        let exited: &[&Block] = if token.is_keyword("return") {
+            &chain
+        } else if token.is_keyword("break") {
+            &chain[chain.len().saturating_sub(1)..]
+        } else {
+            continue;
+        };
+        for block in exited {
+            if !lines.contains(&block.start_line) {
+                lines.push(block.start_line);
+            }
+        }
+    }
+    lines
+}
+
+/// Best case when the loops at `lines` stop during their first iteration.
+pub fn single_pass(cost_tree: &CostNode, lines: &[usize]) -> Option<(Complexity, String)> {
+    if lines.is_empty() {
+        return None;
+    }
+    let best = cost_tree.cost_with_single_pass(lines);
+    let listed: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+    let loops = match listed.len() {
+        1 => format!("the loop at line {}", listed[0]),
+        _ => format!("the loops at lines {}", listed.join(", ")),
+    };
+    Some((
+        best.clone(),
+        format!(
+            "Best case {}: {} can exit on the first iteration; the average case \
+             assumes the exit comes after a fixed fraction of the iterations",
+            best, loops
+        ),
+    ))
+}
+
+/// Whether the function splits its input around a pivot.
+pub fn uses_pivot(tokens: &[Token]) -> bool {
+    tokens.iter().any(|t| {
+        t.kind == TokenKind::Identifier && {
+            let lower = t.text.to_lowercase();
+            PIVOT_NAMES.iter().any(|name| lower.contains(name))
+        }
+    })
+}
+
+/// Worst-case factor of hash lookups inside loops: a lookup degrades to a
+/// scan of the whole table when every key collides, and the table can hold
+/// one entry per iteration of the loops that fill it.
+pub fn hash_collisions(
+    tokens: &[Token],
+    function: &Block,
+    symbols: &mut Symbols,
+) -> Option<(Complexity, String)> {
+    let body = function.own_token_indices();
+    let collections = hash_collections(tokens, &body);
+    if collections.is_empty() {
+        return None;
+    }
+
+    for &i in &body {
+        let token = &tokens[i];
+        let looked_up = if token.is_keyword("in") {
+            // `if x in seen`, but not `for x in seen`
+            let iterated = i >= 2 && tokens[i - 2].is_keyword("for");
+            tokens
+                .get(i + 1)
+                .filter(|next| !iterated && collections.contains(&next.text.as_str()))
+        } else if collections.contains(&token.text.as_str()) && token.kind == TokenKind::Identifier
+        {
+            let method = tokens.get(i + 1).is_some_and(|t| t.is_punct("."))
+                && tokens
+                    .get(i + 2)
+                    .is_some_and(|t| HASH_METHODS.contains(&t.text.as_str()))
+                && lexer::is_call(tokens, i + 2);
+            let subscript = tokens.get(i + 1).is_some_and(|t| t.is_punct("["));
+            (method || subscript).then_some(token)
+        } else {
+            None
+        };
+        let Some(collection) = looked_up else {
+            continue;
+        };
+        let chain = function.enclosing_loops(i);
+        let Some(outermost) = chain.first() else {
+            continue;
+        };
+        let table = cost::loop_iterations(tokens, &chain[..1], symbols).remove(0);
+        if table.is_constant() {
+            continue;
+        }
+        return Some((
+            table.clone(),
+            format!(
+                "Worst case: lookups in '{}' inside the loop at line {} are O(1) on average \
+                 but take {} each when all keys collide",
+                collection.text, outermost.start_line, table
+            ),
+        ));
+    }
+    None
+}
+
+/// Type a value constructs, from the last segment of its path: `HashSet`
+/// for `new java.util.HashSet<>()`, `Counter` for `collections.Counter(xs)`
+/// and `HashMap` for `std::collections::HashMap::new()`.
+fn constructed_type<'a>(value: &[&'a Token]) -> Option<&'a str> {
+    let start = usize::from(
+        value
+            .first()
+            .is_some_and(|t| t.is_keyword("new") || t.is_ident("new")),
+    );
+    let mut segments = Vec::new();
+    let mut rest = &value[start.min(value.len())..];
+    while let [segment, tail @ ..] = rest
+        && segment.kind == TokenKind::Identifier
+    {
+        segments.push(segment.text.as_str());
+        match tail {
+            [separator, after @ ..] if separator.is_punct(".") || separator.is_punct("::") => {
+                rest = after;
+            }
+            _ => break,
+        }
+    }
+    match segments[..] {
+        // Rust constructors name the type before themselves
+        [.., name, "new" | "with_capacity" | "default" | "from"] => Some(name),
+        [.., name] => Some(name),
+        [] => None,
+    }
+}
+
+/// Names bound to hash maps or sets: `seen = set()`, `Map<K, V> m = new
+/// HashMap<>()`, `let mut m = HashMap::new()`, `unordered_map<int, int> m`.
+fn hash_collections<'a>(tokens: &'a [Token], body: &[usize]) -> Vec<&'a str> {
+    let mut names = Vec::new();
+    for (position, &i) in body.iter().enumerate() {
+        let token = &tokens[i];
+        if token.kind != TokenKind::Identifier {
+            continue;
+        }
+        let next = body.get(position + 1).map(|&j| &tokens[j]);
+
+        // `unordered_map<int, int> counts;`
+        if matches!(token.text.as_str(), "unordered_map" | "unordered_set")
+            && next.is_some_and(|t| t.is_punct("<"))
+        {
+            let declared = body[position..]
+                .iter()
+                .map(|&j| &tokens[j])
+                .skip_while(|t| !t.is_punct(">") && !t.is_punct(">>"))
+                .nth(1)
+                .filter(|t| t.kind == TokenKind::Identifier);
+            if let Some(declared) = declared {
+                names.push(declared.text.as_str());
+            }
+            continue;
+        }
+
+        if !next.is_some_and(|t| t.is_punct("=") || t.is_punct(":=")) {
+            continue;
+        }
+        let value: Vec<&Token> = body[position + 2..]
+            .iter()
+            .map(|&j| &tokens[j])
+            .take_while(|t| t.line == token.line && !t.is_punct(";"))
+            .collect();
+        let empty_braces =
+            matches!(value[..], [open, close, ..] if open.is_punct("{") && close.is_punct("}"));
+        let constructed = constructed_type(&value).is_some_and(|name| HASH_TYPES.contains(&name));
+        if empty_braces || constructed {
+            names.push(token.text.as_str());
+        }
+    }
+    names
+}
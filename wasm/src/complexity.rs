@@ -0,0 +1,859 @@
+//! Symbolic complexity expressions.
+//!
+//! A `Complexity` is a sum of terms, each term a product of polynomial,
+//! logarithmic, exponential and factorial factors over named variables.
+//! Sums are simplified to their dominant terms, so `O(n² + n)` becomes
+//! `O(n²)` while `O(V + E)` keeps both terms. Expressions serialize as their
+//! display form (`"O(n³ log n)"`) and parse back from it.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+
+/// Variable used when an analysis has nothing better to name the input size.
+pub const DEFAULT_VARIABLE: &str = "n";
+
+/// Growth rate assigned to `kⁿ` when the base `k` is itself a variable.
+const SYMBOLIC_BASE_RATE: f64 = 1.0e6;
+
+/// Conventional order of variables in products and sums: `n·m`, `V + E`.
+const VARIABLE_ORDER: &[&str] = &["n", "m", "k", "p", "q", "r", "s", "t", "V", "E"];
+
+fn variable_rank(name: &str) -> (usize, &str) {
+    let rank = VARIABLE_ORDER
+        .iter()
+        .position(|v| *v == name)
+        .unwrap_or(VARIABLE_ORDER.len());
+    (rank, name)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Base {
+    Number(u64),
+    Symbol(String),
+}
+
+/// `base^(variable / divisor)`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Exponential {
+    pub base: Base,
+    pub variable: String,
+    pub divisor: u32,
+}
+
+impl Exponential {
+    fn rate(&self) -> f64 {
+        let base = match &self.base {
+            Base::Number(b) => (*b as f64).ln(),
+            Base::Symbol(_) => SYMBOLIC_BASE_RATE,
+        };
+        base / f64::from(self.divisor.max(1))
+    }
+}
+
+/// A product of factors; the empty term is the constant `1`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Term {
+    factorials: BTreeMap<String, u32>,
+    exponentials: Vec<Exponential>,
+    powers: BTreeMap<String, u32>,
+    logs: BTreeMap<String, u32>,
+}
+
+/// How fast a term grows in one variable, most significant factor first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Growth {
+    factorial: u32,
+    exponential: f64,
+    power: u32,
+    log: u32,
+}
+
+impl Growth {
+    fn cmp(&self, other: &Growth) -> Ordering {
+        self.factorial
+            .cmp(&other.factorial)
+            .then(self.exponential.total_cmp(&other.exponential))
+            .then(self.power.cmp(&other.power))
+            .then(self.log.cmp(&other.log))
+    }
+}
+
+impl Term {
+    fn variables(&self) -> BTreeSet<&str> {
+        self.factorials
+            .keys()
+            .chain(self.powers.keys())
+            .chain(self.logs.keys())
+            .map(String::as_str)
+            .chain(self.exponentials.iter().flat_map(|e| {
+                let base = match &e.base {
+                    Base::Symbol(symbol) => Some(symbol.as_str()),
+                    Base::Number(_) => None,
+                };
+                std::iter::once(e.variable.as_str()).chain(base)
+            }))
+            .collect()
+    }
+
+    fn growth_in(&self, variable: &str) -> Growth {
+        let exponential = self
+            .exponentials
+            .iter()
+            .map(|e| match &e.base {
+                _ if e.variable == variable => e.rate(),
+                // `kⁿ` outgrows every fixed power of `k`
+                Base::Symbol(symbol) if symbol == variable => f64::EPSILON,
+                _ => 0.0,
+            })
+            .sum();
+
+        Growth {
+            factorial: self.factorials.get(variable).copied().unwrap_or(0),
+            exponential,
+            power: self.powers.get(variable).copied().unwrap_or(0),
+            log: self.logs.get(variable).copied().unwrap_or(0),
+        }
+    }
+
+    /// Growth when every variable is taken to be the same input size.
+    fn total_growth(&self) -> Growth {
+        Growth {
+            factorial: self.factorials.values().sum(),
+            exponential: self.exponentials.iter().map(Exponential::rate).sum(),
+            power: self.powers.values().sum(),
+            log: self.logs.values().sum(),
+        }
+    }
+
+    /// True when this term grows at least as fast as `other` in every variable.
+    fn dominates(&self, other: &Term) -> bool {
+        let mut variables = self.variables();
+        variables.extend(other.variables());
+        variables
+            .iter()
+            .all(|v| self.growth_in(v).cmp(&other.growth_in(v)) != Ordering::Less)
+    }
+
+    fn times(&self, other: &Term) -> Term {
+        let mut product = self.clone();
+        for (variable, k) in &other.factorials {
+            *product.factorials.entry(variable.clone()).or_default() += k;
+        }
+        for (variable, k) in &other.powers {
+            *product.powers.entry(variable.clone()).or_default() += k;
+        }
+        for (variable, k) in &other.logs {
+            *product.logs.entry(variable.clone()).or_default() += k;
+        }
+        for exponential in &other.exponentials {
+            // 2ⁿ · 3ⁿ = 6ⁿ
+            let same_exponent = product.exponentials.iter_mut().find(|e| {
+                e.variable == exponential.variable
+                    && e.divisor == exponential.divisor
+                    && matches!(
+                        (&e.base, &exponential.base),
+                        (Base::Number(_), Base::Number(_))
+                    )
+            });
+            match (same_exponent, &exponential.base) {
+                (Some(existing), Base::Number(b)) => {
+                    if let Base::Number(a) = existing.base {
+                        existing.base = Base::Number(a.saturating_mul(*b));
+                    }
+                }
+                _ => product.exponentials.push(exponential.clone()),
+            }
+        }
+        product.exponentials.sort();
+        product
+    }
+
+    /// Sort key placing terms over earlier variables first.
+    fn display_rank(&self) -> Vec<(usize, &str)> {
+        let mut ranks: Vec<(usize, &str)> =
+            self.variables().into_iter().map(variable_rank).collect();
+        ranks.sort();
+        ranks
+    }
+
+    fn without(&mut self, name: &str) {
+        self.factorials.remove(name);
+        self.powers.remove(name);
+        self.logs.remove(name);
+        self.exponentials.retain(|e| e.variable != name);
+        for exponential in &mut self.exponentials {
+            // Some constant base; its value no longer shows
+            if exponential.base == Base::Symbol(name.to_string()) {
+                exponential.base = Base::Number(2);
+            }
+        }
+        self.exponentials.sort();
+    }
+
+    fn rename(&mut self, from: &str, to: &str) {
+        fn rename_keys(map: &mut BTreeMap<String, u32>, from: &str, to: &str) {
+            if let Some(k) = map.remove(from) {
+                *map.entry(to.to_string()).or_default() += k;
+            }
+        }
+        rename_keys(&mut self.factorials, from, to);
+        rename_keys(&mut self.powers, from, to);
+        rename_keys(&mut self.logs, from, to);
+        for exponential in &mut self.exponentials {
+            if exponential.variable == from {
+                exponential.variable = to.to_string();
+            }
+            if exponential.base == Base::Symbol(from.to_string()) {
+                exponential.base = Base::Symbol(to.to_string());
+            }
+        }
+        self.exponentials.sort();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Complexity {
+    /// Non-dominated terms, fastest growing first. Never empty.
+    terms: Vec<Term>,
+}
+
+impl Complexity {
+    fn from_terms(terms: Vec<Term>) -> Self {
+        let mut kept: Vec<Term> = Vec::with_capacity(terms.len());
+        for term in terms {
+            if kept.iter().any(|k| k.dominates(&term)) {
+                continue;
+            }
+            kept.retain(|k| !term.dominates(k));
+            kept.push(term);
+        }
+        if kept.is_empty() {
+            kept.push(Term::default());
+        }
+        kept.sort_by(|a, b| {
+            b.total_growth()
+                .cmp(&a.total_growth())
+                .then_with(|| a.display_rank().cmp(&b.display_rank()))
+                .then_with(|| a.cmp(b))
+        });
+        Self { terms: kept }
+    }
+
+    fn from_term(term: Term) -> Self {
+        Self { terms: vec![term] }
+    }
+
+    /// O(1)
+    pub fn constant() -> Self {
+        Self::from_term(Term::default())
+    }
+
+    /// O(log n)
+    pub fn logarithmic() -> Self {
+        Self::log_of(DEFAULT_VARIABLE)
+    }
+
+    /// O(n)
+    pub fn linear() -> Self {
+        Self::variable(DEFAULT_VARIABLE)
+    }
+
+    /// O(n log n)
+    pub fn linearithmic() -> Self {
+        Self::linear().times(&Self::logarithmic())
+    }
+
+    /// O(n²)
+    pub fn quadratic() -> Self {
+        Self::polynomial(2)
+    }
+
+    /// O(n³)
+    pub fn cubic() -> Self {
+        Self::polynomial(3)
+    }
+
+    /// O(n^k)
+    pub fn polynomial(k: u32) -> Self {
+        Self::power(DEFAULT_VARIABLE, k)
+    }
+
+    /// O(baseⁿ)
+    pub fn exponential(base: u64) -> Self {
+        Self::exponential_of(Base::Number(base), DEFAULT_VARIABLE, 1)
+    }
+
+    /// O(n!)
+    pub fn factorial() -> Self {
+        Self::factorial_of(DEFAULT_VARIABLE)
+    }
+
+    pub fn variable(name: &str) -> Self {
+        Self::power(name, 1)
+    }
+
+    pub fn power(name: &str, k: u32) -> Self {
+        let mut term = Term::default();
+        if k > 0 {
+            term.powers.insert(name.to_string(), k);
+        }
+        Self::from_term(term)
+    }
+
+    pub fn log_of(name: &str) -> Self {
+        let mut term = Term::default();
+        term.logs.insert(name.to_string(), 1);
+        Self::from_term(term)
+    }
+
+    /// O(base^(variable / divisor))
+    pub fn exponential_of(base: Base, variable: &str, divisor: u32) -> Self {
+        if base == Base::Number(1) {
+            return Self::constant();
+        }
+        let mut term = Term::default();
+        term.exponentials.push(Exponential {
+            base,
+            variable: variable.to_string(),
+            divisor: divisor.max(1),
+        });
+        Self::from_term(term)
+    }
+
+    pub fn factorial_of(variable: &str) -> Self {
+        let mut term = Term::default();
+        term.factorials.insert(variable.to_string(), 1);
+        Self::from_term(term)
+    }
+
+    pub fn is_constant(&self) -> bool {
+        self.terms.len() == 1 && self.terms[0] == Term::default()
+    }
+
+    /// Whether some term grows exponentially or factorially.
+    pub fn is_exponential(&self) -> bool {
+        self.terms
+            .iter()
+            .any(|t| !t.exponentials.is_empty() || !t.factorials.is_empty())
+    }
+
+    /// Cost of doing `other` once for every unit of `self`.
+    pub fn times(&self, other: &Complexity) -> Complexity {
+        let terms = self
+            .terms
+            .iter()
+            .flat_map(|a| other.terms.iter().map(move |b| a.times(b)))
+            .collect();
+        Self::from_terms(terms)
+    }
+
+    /// Cost of doing `self` and then `other`.
+    pub fn plus(&self, other: &Complexity) -> Complexity {
+        Self::from_terms(self.terms.iter().chain(&other.terms).cloned().collect())
+    }
+
+    /// Variables the expression depends on.
+    pub fn variables(&self) -> BTreeSet<String> {
+        self.terms
+            .iter()
+            .flat_map(|t| t.variables())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Replaces every occurrence of the variable `from` with `to`.
+    pub fn rename(&self, from: &str, to: &str) -> Complexity {
+        let mut terms = self.terms.clone();
+        for term in &mut terms {
+            term.rename(from, to);
+        }
+        Self::from_terms(terms)
+    }
+
+    /// Reads the variable `name` as a constant: `O(n·m)` without `m` is
+    /// `O(n)`, and `O(kⁿ)` without `k` is exponential in `n` alone.
+    pub fn without(&self, name: &str) -> Complexity {
+        let mut terms = self.terms.clone();
+        for term in &mut terms {
+            term.without(name);
+        }
+        Self::from_terms(terms)
+    }
+
+    /// Highest total polynomial degree over all terms.
+    pub fn degree(&self) -> u32 {
+        self.terms
+            .iter()
+            .map(|t| t.powers.values().sum())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// `(k, j)` when the fastest-growing term in `variable` is
+    /// `variable^k log^j variable`, ignoring other variables; `None` when it
+    /// grows exponentially or faster.
+    pub fn polylog_degree(&self, variable: &str) -> Option<(u32, u32)> {
+        let growth = self
+            .terms
+            .iter()
+            .map(|t| t.growth_in(variable))
+            .max_by(|a, b| a.cmp(b))?;
+        (growth.factorial == 0 && growth.exponential == 0.0).then_some((growth.power, growth.log))
+    }
+
+    /// Compares asymptotic growth only, with every variable read as the same
+    /// `n`: `O(m)` and `O(n + k)` are of the same order as `O(n)`. Bounds are
+    /// checked with this rather than `Ord`, which also breaks ties.
+    pub fn order(&self, other: &Complexity) -> Ordering {
+        self.growth().cmp(&other.growth())
+    }
+
+    fn growth(&self) -> Growth {
+        self.terms[0].total_growth()
+    }
+}
+
+impl PartialOrd for Complexity {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Orders by asymptotic growth with every variable read as the same `n`;
+/// ties between different expressions are broken structurally.
+impl Ord for Complexity {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.growth()
+            .cmp(&other.growth())
+            .then_with(|| self.terms.len().cmp(&other.terms.len()))
+            .then_with(|| self.terms.cmp(&other.terms))
+    }
+}
+
+fn superscript(k: u32) -> String {
+    const DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+    k.to_string()
+        .chars()
+        .map(|c| DIGITS[c.to_digit(10).unwrap_or(0) as usize])
+        .collect()
+}
+
+fn format_power(base: &str, k: u32) -> String {
+    match k {
+        1 => base.to_string(),
+        2 | 3 => format!("{}{}", base, superscript(k)),
+        _ => format!("{}^{}", base, k),
+    }
+}
+
+impl fmt::Display for Exponential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.base {
+            Base::Number(b) => write!(f, "{}", b)?,
+            Base::Symbol(s) => write!(f, "{}", s)?,
+        }
+        match (self.variable.as_str(), self.divisor) {
+            ("n", 1) => write!(f, "ⁿ"),
+            (variable, 1) => write!(f, "^{}", variable),
+            (variable, divisor) => write!(f, "^({}/{})", variable, divisor),
+        }
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut powers: Vec<(&String, &u32)> = self.powers.iter().collect();
+        powers.sort_by_key(|(v, _)| variable_rank(v));
+        let mut logs: Vec<(&String, &u32)> = self.logs.iter().collect();
+        logs.sort_by_key(|(v, _)| variable_rank(v));
+
+        let mut factors: Vec<String> = Vec::new();
+        factors.extend(powers.iter().map(|(v, k)| format_power(v, **k)));
+        factors.extend(self.exponentials.iter().map(|e| e.to_string()));
+        for (variable, k) in &self.factorials {
+            factors.extend(std::iter::repeat_n(format!("{}!", variable), *k as usize));
+        }
+
+        let logs: Vec<String> = logs
+            .iter()
+            .map(|(v, k)| format!("{} {}", format_power("log", **k), v))
+            .collect();
+
+        match (factors.is_empty(), logs.is_empty()) {
+            (true, true) => write!(f, "1"),
+            (true, false) => write!(f, "{}", logs.join(" ")),
+            (false, true) => write!(f, "{}", factors.join("·")),
+            (false, false) => write!(f, "{} {}", factors.join("·"), logs.join(" ")),
+        }
+    }
+}
+
+impl fmt::Display for Complexity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let terms: Vec<String> = self.terms.iter().map(|t| t.to_string()).collect();
+        write!(f, "O({})", terms.join(" + "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseComplexityError(String);
+
+impl fmt::Display for ParseComplexityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid complexity expression: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseComplexityError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Lexeme {
+    Number(u64),
+    Ident(String),
+    /// Superscript digits such as `²`
+    SuperNumber(u32),
+    /// Superscript `ⁿ`
+    SuperN,
+    Symbol(char),
+}
+
+fn lex_expression(input: &str) -> Result<Vec<Lexeme>, ParseComplexityError> {
+    let mut lexemes = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() {
+            let mut digits = String::new();
+            while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                digits.push(d);
+                chars.next();
+            }
+            let value = digits
+                .parse()
+                .map_err(|_| ParseComplexityError(input.to_string()))?;
+            lexemes.push(Lexeme::Number(value));
+        } else if c == 'ⁿ' {
+            chars.next();
+            lexemes.push(Lexeme::SuperN);
+        } else if let Some(first) = "⁰¹²³⁴⁵⁶⁷⁸⁹".chars().position(|s| s == c) {
+            chars.next();
+            let mut value = first as u32;
+            while let Some(next) = chars
+                .peek()
+                .and_then(|&d| "⁰¹²³⁴⁵⁶⁷⁸⁹".chars().position(|s| s == d))
+            {
+                value = value * 10 + next as u32;
+                chars.next();
+            }
+            lexemes.push(Lexeme::SuperNumber(value));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut name = String::new();
+            while let Some(&d) = chars.peek().filter(|d| d.is_alphanumeric() || **d == '_') {
+                if "⁰¹²³⁴⁵⁶⁷⁸⁹ⁿ".contains(d) {
+                    break;
+                }
+                name.push(d);
+                chars.next();
+            }
+            // `nlogn` is `n log n`
+            let mut rest = name.as_str();
+            while let Some(at) = rest.find("log") {
+                if at > 0 {
+                    lexemes.push(Lexeme::Ident(rest[..at].to_string()));
+                }
+                lexemes.push(Lexeme::Ident("log".to_string()));
+                rest = &rest[at + 3..];
+            }
+            if !rest.is_empty() {
+                lexemes.push(Lexeme::Ident(rest.to_string()));
+            }
+        } else if "+·*^()/!".contains(c) {
+            chars.next();
+            lexemes.push(Lexeme::Symbol(c));
+        } else {
+            return Err(ParseComplexityError(input.to_string()));
+        }
+    }
+
+    Ok(lexemes)
+}
+
+/// Variables are a letter and optional digits: `n`, `V`, `n8`.
+fn is_variable(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(char::is_alphabetic) && chars.all(|c| c.is_ascii_digit())
+}
+
+struct ExpressionParser {
+    lexemes: Vec<Lexeme>,
+    pos: usize,
+}
+
+impl ExpressionParser {
+    fn peek(&self) -> Option<&Lexeme> {
+        self.lexemes.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Lexeme> {
+        let lexeme = self.lexemes.get(self.pos).cloned();
+        self.pos += 1;
+        lexeme
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        if self.peek() == Some(&Lexeme::Symbol(symbol)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Option<()> {
+        self.eat(symbol).then_some(())
+    }
+
+    fn ident(&mut self) -> Option<String> {
+        match self.next()? {
+            Lexeme::Ident(name) if is_variable(&name) => Some(name),
+            _ => None,
+        }
+    }
+
+    fn sum(&mut self) -> Option<Complexity> {
+        let mut total = self.product()?;
+        while self.eat('+') {
+            total = total.plus(&self.product()?);
+        }
+        Some(total)
+    }
+
+    fn product(&mut self) -> Option<Complexity> {
+        let mut product = self.factor()?;
+        loop {
+            match self.peek() {
+                None | Some(Lexeme::Symbol('+')) | Some(Lexeme::Symbol(')')) => break,
+                Some(Lexeme::Symbol('·')) | Some(Lexeme::Symbol('*')) => self.pos += 1,
+                _ => {}
+            }
+            product = product.times(&self.factor()?);
+        }
+        Some(product)
+    }
+
+    /// Parses `v` or `(v/d)` after a `^`.
+    fn exponent(&mut self) -> Option<(String, u32)> {
+        if self.eat('(') {
+            let variable = self.ident()?;
+            self.expect('/')?;
+            let divisor = match self.next()? {
+                Lexeme::Number(d) => u32::try_from(d).ok()?,
+                _ => return None,
+            };
+            self.expect(')')?;
+            Some((variable, divisor))
+        } else {
+            Some((self.ident()?, 1))
+        }
+    }
+
+    fn factor(&mut self) -> Option<Complexity> {
+        match self.next()? {
+            Lexeme::Symbol('(') => {
+                let inner = self.sum()?;
+                self.expect(')')?;
+                Some(inner)
+            }
+            Lexeme::Ident(name) if name == "log" => {
+                let k = match self.peek() {
+                    Some(Lexeme::SuperNumber(k)) => {
+                        let k = *k;
+                        self.pos += 1;
+                        k
+                    }
+                    Some(Lexeme::Symbol('^')) => {
+                        self.pos += 1;
+                        match self.next()? {
+                            Lexeme::Number(k) => u32::try_from(k).ok()?,
+                            _ => return None,
+                        }
+                    }
+                    _ => 1,
+                };
+                let variable = if self.eat('(') {
+                    let variable = self.ident()?;
+                    self.expect(')')?;
+                    variable
+                } else {
+                    self.ident()?
+                };
+                let log = Complexity::log_of(&variable);
+                Some((1..k).fold(log.clone(), |acc, _| acc.times(&log)))
+            }
+            Lexeme::Number(value) => match self.peek() {
+                Some(Lexeme::SuperN) => {
+                    self.pos += 1;
+                    Some(Complexity::exponential_of(
+                        Base::Number(value),
+                        DEFAULT_VARIABLE,
+                        1,
+                    ))
+                }
+                Some(Lexeme::Symbol('^')) => {
+                    self.pos += 1;
+                    let (variable, divisor) = self.exponent()?;
+                    Some(Complexity::exponential_of(
+                        Base::Number(value),
+                        &variable,
+                        divisor,
+                    ))
+                }
+                // Constant coefficients do not change the bound
+                _ => Some(Complexity::constant()),
+            },
+            // Functions other than `log`, such as `sqrt(n)`, are not
+            // supported; neither are words like `size`
+            Lexeme::Ident(name) if !is_variable(&name) => None,
+            Lexeme::Ident(_) if self.peek() == Some(&Lexeme::Symbol('(')) => None,
+            Lexeme::Ident(name) => match self.peek().cloned() {
+                Some(Lexeme::Symbol('!')) => {
+                    self.pos += 1;
+                    Some(Complexity::factorial_of(&name))
+                }
+                Some(Lexeme::SuperNumber(k)) => {
+                    self.pos += 1;
+                    Some(Complexity::power(&name, k))
+                }
+                Some(Lexeme::SuperN) => {
+                    self.pos += 1;
+                    Some(Complexity::exponential_of(
+                        Base::Symbol(name),
+                        DEFAULT_VARIABLE,
+                        1,
+                    ))
+                }
+                Some(Lexeme::Symbol('^')) => {
+                    self.pos += 1;
+                    if let Some(Lexeme::Number(k)) = self.peek().cloned() {
+                        self.pos += 1;
+                        return Some(Complexity::power(&name, u32::try_from(k).ok()?));
+                    }
+                    let (variable, divisor) = self.exponent()?;
+                    Some(Complexity::exponential_of(
+                        Base::Symbol(name),
+                        &variable,
+                        divisor,
+                    ))
+                }
+                _ => Some(Complexity::variable(&name)),
+            },
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Complexity {
+    type Err = ParseComplexityError;
+
+    /// Parses the display form, with or without the surrounding `O(...)`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let inner = trimmed
+            .strip_prefix("O(")
+            .and_then(|rest| rest.strip_suffix(')'))
+            .unwrap_or(trimmed);
+
+        let mut parser = ExpressionParser {
+            lexemes: lex_expression(inner)?,
+            pos: 0,
+        };
+        match parser.sum() {
+            Some(complexity) if parser.pos == parser.lexemes.len() => Ok(complexity),
+            _ => Err(ParseComplexityError(s.to_string())),
+        }
+    }
+}
+
+impl Serialize for Complexity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Complexity {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Complexity {
+        text.parse()
+            .unwrap_or_else(|error| panic!("{}: {}", text, error))
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let complexities = [
+            Complexity::constant(),
+            Complexity::logarithmic(),
+            Complexity::linear(),
+            Complexity::linearithmic(),
+            Complexity::quadratic(),
+            Complexity::polynomial(5),
+            Complexity::exponential(2),
+            Complexity::exponential(3),
+            Complexity::factorial(),
+            Complexity::variable("m"),
+            Complexity::linear().times(&Complexity::variable("m")),
+            Complexity::variable("V").plus(&Complexity::variable("E")),
+            Complexity::log_of("n").times(&Complexity::log_of("n")),
+            Complexity::exponential_of(Base::Number(2), "n", 2),
+            Complexity::exponential_of(Base::Symbol("k".to_string()), "n", 1),
+            Complexity::quadratic().plus(&Complexity::variable("m")),
+            Complexity::variable("n8"),
+        ];
+        for complexity in complexities {
+            let text = complexity.to_string();
+            assert_eq!(parse(&text), complexity, "{}", text);
+        }
+    }
+
+    #[test]
+    fn parses_alternative_spellings() {
+        assert_eq!(parse("n log n"), Complexity::linearithmic());
+        assert_eq!(parse("O(n*log(n))"), Complexity::linearithmic());
+        assert_eq!(parse("O(n^2)"), Complexity::quadratic());
+        assert_eq!(parse("O(2^n)"), Complexity::exponential(2));
+        assert_eq!(parse("O(n!)"), Complexity::factorial());
+    }
+
+    #[test]
+    fn splits_run_together_logarithms() {
+        assert_eq!(parse("O(nlogn)"), Complexity::linearithmic());
+        assert_eq!(parse("O(logn)"), Complexity::logarithmic());
+    }
+
+    #[test]
+    fn rejects_unknown_functions_and_words() {
+        for text in ["O(sqrt(n))", "O(f(n))", "O(size)", "O(nm)", "O(n log size)"] {
+            assert!(text.parse::<Complexity>().is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn without_reads_a_variable_as_constant() {
+        assert_eq!(parse("O(n·m)").without("m"), Complexity::linear());
+        assert_eq!(parse("O(2^n)").without("n"), Complexity::constant());
+        assert_eq!(parse("O(n log m)").without("n"), parse("O(log m)"));
+    }
+}
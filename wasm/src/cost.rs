@@ -0,0 +1,458 @@
+//! Per-block cost model.
+//!
+//! Walks the block tree of a function and costs every loop from its bound:
+//! nested blocks multiply, sibling blocks add. Building a collection takes
+//! time in proportion to its size, so allocations, copies and
+//! comprehensions count as well. The resulting tree is kept so callers can
+//! see which block dominates a function's complexity.
+
+use crate::blocks::{Block, BlockKind};
+use crate::complexity::Complexity;
+use crate::lexer::{self, Token, TokenKind};
+use crate::loops::{self, LoopBound, Pointer, SizeSource, Symbols};
+use crate::space;
+use serde::{Deserialize, Serialize};
+
+/// Cost of one block of a function.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostNode {
+    /// `function`, `loop`, `conditional` or `block`.
+    kind: String,
+    line_start: usize,
+    line_end: usize,
+    /// Iterations per entry into the block; `O(1)` for anything but loops.
+    iterations: Complexity,
+    /// Cost of the block each time its parent runs it, or across all runs
+    /// of the parent when `amortized` is set.
+    cost: Complexity,
+    amortized: bool,
+    /// Whether this block has the highest cost among its siblings.
+    dominant: bool,
+    children: Vec<CostNode>,
+}
+
+impl CostNode {
+    /// `function`, `loop`, `conditional` or `block`.
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    pub fn line_start(&self) -> usize {
+        self.line_start
+    }
+
+    pub fn line_end(&self) -> usize {
+        self.line_end
+    }
+
+    /// Iterations per entry into the block.
+    pub fn iterations(&self) -> &Complexity {
+        &self.iterations
+    }
+
+    /// Whether `cost` covers all runs of the parent rather than one.
+    pub fn is_amortized(&self) -> bool {
+        self.amortized
+    }
+
+    /// Whether this block costs the most among its siblings.
+    pub fn is_dominant(&self) -> bool {
+        self.dominant
+    }
+
+    pub fn cost(&self) -> &Complexity {
+        &self.cost
+    }
+
+    pub fn children(&self) -> &[CostNode] {
+        &self.children
+    }
+
+    /// The child that outweighs its siblings, if the children differ in cost.
+    pub fn dominant_child(&self) -> Option<&CostNode> {
+        let dominant = self.children.iter().find(|child| child.dominant)?;
+        self.children
+            .iter()
+            .any(|child| child.cost < dominant.cost)
+            .then_some(dominant)
+    }
+
+    /// Cost when each loop starting at one of `lines` stops during its
+    /// first iteration, as a search does when it finds its target at once.
+    pub fn cost_with_single_pass(&self, lines: &[usize]) -> Complexity {
+        let mut repeated = Complexity::constant();
+        let mut amortized = Complexity::constant();
+        for child in &self.children {
+            let cost = child.cost_with_single_pass(lines);
+            if child.amortized {
+                amortized = amortized.plus(&cost);
+            } else {
+                repeated = repeated.plus(&cost);
+            }
+        }
+        if self.kind == "loop" && !lines.contains(&self.line_start) {
+            repeated = self.iterations.times(&repeated);
+        }
+        repeated.plus(&amortized)
+    }
+
+    /// Short description such as `loop at lines 3-7`.
+    pub fn describe(&self) -> String {
+        if self.line_start == self.line_end {
+            format!("{} at line {}", self.kind, self.line_start)
+        } else {
+            format!(
+                "{} at lines {}-{}",
+                self.kind, self.line_start, self.line_end
+            )
+        }
+    }
+}
+
+/// Methods appending to a growable array: O(1) amortized, since the array
+/// doubles its capacity whenever it fills up.
+const APPENDING_METHODS: &[&str] = &[
+    "append",
+    "push",
+    "push_back",
+    "emplace_back",
+    "add",
+    "extend",
+];
+
+/// A loop enclosing the one being costed.
+#[derive(Clone)]
+struct Enclosing<'a> {
+    block: &'a Block,
+    index: Option<String>,
+    symbol: String,
+    iterations: Complexity,
+    /// Whether the index multiplies or divides, so that it takes the
+    /// values 1, 2, 4, ... up to its bound.
+    geometric: bool,
+}
+
+/// Builds the cost tree of `function`. Loops that are not plain linear
+/// scans are noted in `details`, and so are collections built in time
+/// proportional to their size.
+pub fn cost_tree(
+    tokens: &[Token],
+    function: &Block,
+    symbols: &mut Symbols,
+    language: &str,
+    details: &mut Vec<String>,
+) -> CostNode {
+    let built = space::construction(tokens, function, symbols, language);
+    let (children, repeated, amortized) =
+        children_cost(tokens, function, &[], &built, symbols, details);
+    CostNode {
+        kind: "function".to_string(),
+        line_start: function.start_line,
+        line_end: function.end_line,
+        iterations: Complexity::constant(),
+        cost: repeated.plus(&amortized),
+        amortized: false,
+        dominant: true,
+        children,
+    }
+}
+
+/// Iterations of each loop in `chain`, a run of nested loops listed
+/// outermost first. A loop bounded by an outer index (`for j in range(i)`)
+/// takes that index's bound.
+pub fn loop_iterations(
+    tokens: &[Token],
+    chain: &[&Block],
+    symbols: &mut Symbols,
+) -> Vec<Complexity> {
+    let mut outer: Vec<(Option<String>, String)> = Vec::new();
+    let mut iterations = Vec::new();
+    for block in chain {
+        let bound = symbols.fold_constants(loops::loop_bound(tokens, block));
+        let index = loops::loop_index(tokens, block);
+        let symbol = match bound.source() {
+            Some(SizeSource::Value(name)) => match outer
+                .iter()
+                .rev()
+                .find(|(index, _)| index.as_deref() == Some(name.as_str()))
+            {
+                Some((_, symbol)) => symbol.clone(),
+                None => symbols.symbol_for(&SizeSource::Value(name.clone())),
+            },
+            Some(source) => symbols.symbol_for(source),
+            None => symbols.primary(),
+        };
+        let assumed = bound.source().is_some() && symbols.assumption(&symbol).is_some();
+        iterations.push(match bound {
+            _ if assumed => Complexity::constant(),
+            LoopBound::Constant(_) => Complexity::constant(),
+            LoopBound::Linear(_) => Complexity::variable(&symbol),
+            LoopBound::Logarithmic(_) => Complexity::log_of(&symbol),
+        });
+        outer.push((index, symbol));
+    }
+    iterations
+}
+
+/// Notes on appends inside loops of `function`, one per collection: each
+/// costs O(1) amortized even though an occasional append copies the array.
+pub fn amortized_appends(tokens: &[Token], function: &Block) -> Vec<String> {
+    let mut notes = Vec::new();
+    let mut seen: Vec<&str> = Vec::new();
+    for i in function.own_token_indices() {
+        let [receiver, dot, method] = [i, i + 1, i + 2].map(|j| tokens.get(j));
+        let (Some(receiver), Some(dot), Some(method)) = (receiver, dot, method) else {
+            continue;
+        };
+        if receiver.kind != TokenKind::Identifier
+            || !dot.is_punct(".")
+            || !APPENDING_METHODS.contains(&method.text.as_str())
+            || !lexer::is_call(tokens, i + 2)
+            || seen.contains(&receiver.text.as_str())
+        {
+            continue;
+        }
+        let Some(innermost) = function.enclosing_loops(i).pop() else {
+            continue;
+        };
+        seen.push(&receiver.text);
+        notes.push(format!(
+            "Amortized: '{}.{}' in the loop at line {} is O(1) amortized; the occasional \
+             resize is paid for by the appends before it",
+            receiver.text, method.text, innermost.start_line
+        ));
+    }
+    notes
+}
+
+/// Costs the children of `block`, returning their nodes along with the part
+/// of their cost repeated on every iteration of the enclosing loop and the
+/// part amortized across all of them (a pointer that is never reset).
+/// `built` lists the collections the function builds, by token index; those
+/// built in `block` itself, loop headers included, are repeated work.
+fn children_cost(
+    tokens: &[Token],
+    block: &Block,
+    enclosing: &[Enclosing],
+    built: &[(usize, Complexity)],
+    symbols: &mut Symbols,
+    details: &mut Vec<String>,
+) -> (Vec<CostNode>, Complexity, Complexity) {
+    let mut nodes = Vec::new();
+    let mut repeated = Complexity::constant();
+    let mut amortized = Complexity::constant();
+
+    let body = block.header.end.max(block.span.start)..block.span.end;
+    for (index, time) in built.iter().filter(|(index, _)| {
+        body.contains(index)
+            && !block.children.iter().any(|child| {
+                child.span.contains(index) && (child.is_function() || !child.header.contains(index))
+            })
+    }) {
+        // `[1] * (i + 1)` inside `for i in range(n)` takes the bound of `i`
+        let mut time = time.clone();
+        for variable in time.variables() {
+            if let Some(SizeSource::Value(name)) = symbols.source(&variable)
+                && let Some(outer) = enclosing
+                    .iter()
+                    .rev()
+                    .find(|outer| outer.index.as_deref() == Some(name.as_str()))
+            {
+                time = time.rename(&variable, &outer.symbol);
+            }
+        }
+        details.push(format!(
+            "Building the collection at line {} takes {}",
+            tokens[*index].line, time
+        ));
+        repeated = repeated.plus(&time);
+    }
+
+    for child in &block.children {
+        if child.is_function() {
+            continue;
+        }
+        if child.kind != BlockKind::Loop {
+            let (children, inner_repeated, inner_amortized) =
+                children_cost(tokens, child, enclosing, built, symbols, details);
+            repeated = repeated.plus(&inner_repeated);
+            amortized = amortized.plus(&inner_amortized);
+            // Blocks without loops cost O(1) and only clutter the tree
+            if !children.is_empty() {
+                let kind = match child.kind {
+                    BlockKind::Conditional => "conditional",
+                    _ => "block",
+                };
+                nodes.push(CostNode {
+                    kind: kind.to_string(),
+                    line_start: child.start_line,
+                    line_end: child.end_line,
+                    iterations: Complexity::constant(),
+                    cost: inner_repeated.plus(&inner_amortized),
+                    amortized: false,
+                    dominant: false,
+                    children,
+                });
+            }
+            continue;
+        }
+
+        let bound = symbols.fold_constants(loops::loop_bound(tokens, child));
+        let index = loops::loop_index(tokens, child);
+
+        // `for j in range(i)`: bounded by an outer index, so by its bound;
+        // a window such as `range(i, i + 3)` already has a constant bound
+        let outer = bound.source().and_then(|source| match source {
+            SizeSource::Value(name) => enclosing
+                .iter()
+                .rev()
+                .find(|outer| outer.index.as_deref() == Some(name.as_str())),
+            SizeSource::Length(_) => None,
+        });
+        // `for (j = 0; j < i; j++)` right inside `for (i = 1; i < n; i *= 2)`
+        // runs 1 + 2 + 4 + ... + n times in total, which is O(n)
+        let geometric = outer.filter(|outer| {
+            outer.geometric
+                && matches!(bound, LoopBound::Linear(_))
+                && enclosing
+                    .last()
+                    .is_some_and(|last| std::ptr::eq(last.block, outer.block))
+        });
+        let symbol = match (outer, bound.source()) {
+            (Some(outer), _) => {
+                let shape = match bound {
+                    LoopBound::Linear(_) if geometric.is_some() => " (geometric sum)",
+                    LoopBound::Linear(_) => " (triangular)",
+                    _ => "",
+                };
+                details.push(format!(
+                    "Loop at line {} is bounded by outer index '{}'{}",
+                    child.start_line,
+                    outer.index.as_deref().unwrap_or_default(),
+                    shape
+                ));
+                outer.symbol.clone()
+            }
+            (None, Some(source)) => symbols.symbol_for(source),
+            (None, None) => symbols.primary(),
+        };
+
+        let assumption = bound
+            .source()
+            .and_then(|_| symbols.assumption(&symbol))
+            .cloned();
+        let iterations = match bound {
+            _ if let Some(assumption) = assumption => {
+                details.push(format!(
+                    "Loop at line {} is constant: a comment assumes {}",
+                    child.start_line, assumption
+                ));
+                Complexity::constant()
+            }
+            LoopBound::Constant(_) => {
+                details.push(format!(
+                    "Loop at line {} has a constant bound",
+                    child.start_line
+                ));
+                Complexity::constant()
+            }
+            LoopBound::Linear(_) => Complexity::variable(&symbol),
+            LoopBound::Logarithmic(_) => {
+                details.push(format!(
+                    "Loop at line {} multiplies or divides its index (logarithmic)",
+                    child.start_line
+                ));
+                Complexity::log_of(&symbol)
+            }
+        };
+
+        // Sliding windows and monotonic stacks: a `while` inside another
+        // loop that moves a pointer forward, or pops what the outer loop
+        // pushed, runs once per outer iteration in total
+        let pointer = enclosing.last().and_then(|outer| {
+            loops::monotonic_pointer(tokens, child)
+                .filter(|pointer| {
+                    !outer.iterations.is_constant()
+                        && !loops::is_reset(tokens, outer.block, child, pointer.name())
+                })
+                .map(|pointer| (outer, pointer))
+        });
+        let iterations = match &pointer {
+            Some((outer, _)) => outer.iterations.clone(),
+            None => iterations,
+        };
+
+        let mut nested = enclosing.to_vec();
+        nested.push(Enclosing {
+            block: child,
+            index: index.clone(),
+            symbol: symbol.clone(),
+            iterations: iterations.clone(),
+            geometric: iterations == Complexity::log_of(&symbol),
+        });
+        let (children, inner_repeated, inner_amortized) =
+            children_cost(tokens, child, &nested, built, symbols, details);
+        let total = iterations.times(&inner_repeated).plus(&inner_amortized);
+
+        // Two pointers: an inner loop picking up where it left off only
+        // advances its index once over the whole outer loop
+        let never_reset = pointer.is_some()
+            || match (enclosing.last(), &index) {
+                (Some(outer), Some(index)) => {
+                    matches!(bound, LoopBound::Linear(_))
+                        && loops::continues_index(tokens, child)
+                        && outer.index.as_deref() != Some(index.as_str())
+                        && !loops::is_reset(tokens, outer.block, child, index)
+                }
+                _ => false,
+            };
+        if let Some((outer, pointer)) = &pointer {
+            let progress = match pointer {
+                Pointer::Advances(name) => format!("only moves '{}' forward", name),
+                Pointer::Pops(name) => format!("pops from '{}'", name),
+            };
+            details.push(format!(
+                "Amortized: loop at line {} {} and never resets it, so it runs {} times \
+                 in total across the loop at line {}",
+                child.start_line, progress, iterations, outer.block.start_line
+            ));
+            amortized = amortized.plus(&total);
+        } else if let Some(outer) = geometric {
+            details.push(format!(
+                "Amortized: loop at line {} runs up to '{}', which doubles or halves, so it \
+                 runs {} times in total across the loop at line {}",
+                child.start_line,
+                outer.index.as_deref().unwrap_or_default(),
+                iterations,
+                outer.block.start_line
+            ));
+            amortized = amortized.plus(&total);
+        } else if never_reset {
+            details.push(format!(
+                "Amortized: loop at line {} never resets '{}', so it runs at most {} times overall",
+                child.start_line,
+                index.as_deref().unwrap_or_default(),
+                iterations
+            ));
+            amortized = amortized.plus(&total);
+        } else {
+            repeated = repeated.plus(&total);
+        }
+
+        nodes.push(CostNode {
+            kind: "loop".to_string(),
+            line_start: child.start_line,
+            line_end: child.end_line,
+            iterations,
+            cost: total,
+            amortized: never_reset || geometric.is_some(),
+            dominant: false,
+            children,
+        });
+    }
+
+    if let Some(dominant) = nodes.iter_mut().rev().max_by(|a, b| a.cost.cmp(&b.cost)) {
+        dominant.dominant = true;
+    }
+    (nodes, repeated, amortized)
+}
@@ -0,0 +1,147 @@
+//! Complexity changes between two versions of a file.
+//!
+//! Functions are matched by name and parameter list first, then by name
+//! alone, so that a function keeps its identity when a parameter is added
+//! and overloads are told apart. A matched function improved or regressed
+//! when its complexity changed order; renaming a variable (`O(n)` to `O(m)`)
+//! is no change.
+
+use crate::complexity::Complexity;
+use crate::{AnalysisResult, ComplexityAnalyzer, FunctionAnalysis};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// One function in the old version, the new version or both.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionChange {
+    function: String,
+    /// Parameters in the new version, or the old one for removed functions.
+    parameters: Vec<String>,
+    old_complexity: Option<Complexity>,
+    new_complexity: Option<Complexity>,
+    old_line: Option<usize>,
+    new_line: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ComplexityDiff {
+    added: Vec<FunctionChange>,
+    removed: Vec<FunctionChange>,
+    improved: Vec<FunctionChange>,
+    regressed: Vec<FunctionChange>,
+}
+
+impl FunctionChange {
+    pub fn function(&self) -> &str {
+        &self.function
+    }
+
+    pub fn parameters(&self) -> &[String] {
+        &self.parameters
+    }
+
+    /// Complexity in the old version; `None` for added functions.
+    pub fn old_complexity(&self) -> Option<&Complexity> {
+        self.old_complexity.as_ref()
+    }
+
+    /// Complexity in the new version; `None` for removed functions.
+    pub fn new_complexity(&self) -> Option<&Complexity> {
+        self.new_complexity.as_ref()
+    }
+
+    pub fn old_line(&self) -> Option<usize> {
+        self.old_line
+    }
+
+    pub fn new_line(&self) -> Option<usize> {
+        self.new_line
+    }
+}
+
+impl ComplexityDiff {
+    /// Functions only in the new version.
+    pub fn added(&self) -> &[FunctionChange] {
+        &self.added
+    }
+
+    /// Functions only in the old version.
+    pub fn removed(&self) -> &[FunctionChange] {
+        &self.removed
+    }
+
+    /// Functions that grow more slowly than they did.
+    pub fn improved(&self) -> &[FunctionChange] {
+        &self.improved
+    }
+
+    /// Functions that grow faster than they did.
+    pub fn regressed(&self) -> &[FunctionChange] {
+        &self.regressed
+    }
+}
+
+impl ComplexityAnalyzer {
+    /// Analyzes both versions of a file and compares their functions.
+    pub fn diff(&self, old_code: &str, new_code: &str) -> ComplexityDiff {
+        diff_analyses(&self.analyze(old_code), &self.analyze(new_code))
+    }
+}
+
+/// Compares the functions of two analyses of the same file.
+pub fn diff_analyses(old: &AnalysisResult, new: &AnalysisResult) -> ComplexityDiff {
+    let old_functions = old.functions();
+    let new_functions = new.functions();
+    let mut matched: Vec<Option<usize>> = vec![None; new_functions.len()];
+    let mut taken = vec![false; old_functions.len()];
+
+    // Same name and parameters first, then the same name alone
+    for exact in [true, false] {
+        for (position, function) in new_functions.iter().enumerate() {
+            if matched[position].is_some() {
+                continue;
+            }
+            let found = old_functions.iter().enumerate().position(|(index, old)| {
+                !taken[index]
+                    && old.function() == function.function()
+                    && (!exact || old.parameters() == function.parameters())
+            });
+            if let Some(index) = found {
+                taken[index] = true;
+                matched[position] = Some(index);
+            }
+        }
+    }
+
+    let mut diff = ComplexityDiff::default();
+    for (function, matched) in new_functions.iter().zip(matched) {
+        let Some(index) = matched else {
+            diff.added.push(change(None, Some(function)));
+            continue;
+        };
+        let old = &old_functions[index];
+        match function.complexity().order(old.complexity()) {
+            Ordering::Greater => diff.regressed.push(change(Some(old), Some(function))),
+            Ordering::Less => diff.improved.push(change(Some(old), Some(function))),
+            Ordering::Equal => {}
+        }
+    }
+    for (old, taken) in old_functions.iter().zip(taken) {
+        if !taken {
+            diff.removed.push(change(Some(old), None));
+        }
+    }
+    diff
+}
+
+fn change(old: Option<&FunctionAnalysis>, new: Option<&FunctionAnalysis>) -> FunctionChange {
+    let current = new.or(old).expect("a change has at least one version");
+    FunctionChange {
+        function: current.function().to_string(),
+        parameters: current.parameters().to_vec(),
+        old_complexity: old.map(|f| f.complexity().clone()),
+        new_complexity: new.map(|f| f.complexity().clone()),
+        old_line: old.map(FunctionAnalysis::line_start),
+        new_line: new.map(FunctionAnalysis::line_start),
+    }
+}
@@ -0,0 +1,726 @@
+//! Language-aware tokenizer.
+//!
+//! Every detector in the analyzer works on the token stream produced here
+//! instead of raw source text, so comments, string literals and char
+//! literals can no longer change the verdict.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Identifier,
+    Keyword,
+    Number,
+    String,
+    Char,
+    Comment,
+    /// C preprocessor line such as `#include <vector>`.
+    Directive,
+    Punctuation,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+    /// 1-based line of the first character.
+    pub line: usize,
+    /// 1-based column (in characters) of the first character.
+    pub column: usize,
+    /// 1-based line of the last character.
+    pub end_line: usize,
+    /// 1-based column (in characters) one past the last character.
+    pub end_column: usize,
+}
+
+impl Token {
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        self.kind == TokenKind::Keyword && self.text == keyword
+    }
+
+    pub fn is_punct(&self, punct: &str) -> bool {
+        self.kind == TokenKind::Punctuation && self.text == punct
+    }
+
+    pub fn is_ident(&self, name: &str) -> bool {
+        self.kind == TokenKind::Identifier && self.text == name
+    }
+
+    /// Comments and preprocessor directives carry no code.
+    pub fn is_trivia(&self) -> bool {
+        matches!(self.kind, TokenKind::Comment | TokenKind::Directive)
+    }
+}
+
+/// Lexical rules that differ between the supported languages.
+struct Syntax {
+    line_comment: &'static str,
+    block_comment: bool,
+    nested_block_comments: bool,
+    single_quote_is_char: bool,
+    triple_quoted_strings: bool,
+    template_literals: bool,
+    regex_literals: bool,
+    rust_raw_strings: bool,
+    multiline_strings: bool,
+    lifetimes: bool,
+    preprocessor: bool,
+    keywords: &'static [&'static str],
+}
+
+const PYTHON_KEYWORDS: &[&str] = &[
+    "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif",
+    "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda",
+    "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with", "yield",
+];
+
+const JS_KEYWORDS: &[&str] = &[
+    "async",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "export",
+    "extends",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "let",
+    "new",
+    "of",
+    "return",
+    "switch",
+    "this",
+    "throw",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "yield",
+];
+
+const JAVA_KEYWORDS: &[&str] = &[
+    "abstract",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "continue",
+    "default",
+    "do",
+    "else",
+    "extends",
+    "final",
+    "finally",
+    "for",
+    "if",
+    "implements",
+    "import",
+    "instanceof",
+    "interface",
+    "new",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "switch",
+    "synchronized",
+    "this",
+    "throw",
+    "throws",
+    "try",
+    "while",
+];
+
+const C_KEYWORDS: &[&str] = &[
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "for",
+    "goto",
+    "if",
+    "inline",
+    "namespace",
+    "new",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "template",
+    "this",
+    "throw",
+    "try",
+    "typedef",
+    "typename",
+    "union",
+    "using",
+    "virtual",
+    "while",
+];
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "else", "enum", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "self", "static", "struct", "trait", "type", "unsafe", "use", "where", "while",
+];
+
+impl Syntax {
+    fn for_language(language: &str) -> Self {
+        match language.to_lowercase().as_str() {
+            "python" => Syntax {
+                line_comment: "#",
+                block_comment: false,
+                nested_block_comments: false,
+                single_quote_is_char: false,
+                triple_quoted_strings: true,
+                template_literals: false,
+                regex_literals: false,
+                rust_raw_strings: false,
+                multiline_strings: false,
+                lifetimes: false,
+                preprocessor: false,
+                keywords: PYTHON_KEYWORDS,
+            },
+            "javascript" | "typescript" => Syntax {
+                line_comment: "//",
+                block_comment: true,
+                nested_block_comments: false,
+                single_quote_is_char: false,
+                triple_quoted_strings: false,
+                template_literals: true,
+                regex_literals: true,
+                rust_raw_strings: false,
+                multiline_strings: false,
+                lifetimes: false,
+                preprocessor: false,
+                keywords: JS_KEYWORDS,
+            },
+            "java" => Syntax {
+                line_comment: "//",
+                block_comment: true,
+                nested_block_comments: false,
+                single_quote_is_char: true,
+                triple_quoted_strings: true,
+                template_literals: false,
+                regex_literals: false,
+                rust_raw_strings: false,
+                multiline_strings: false,
+                lifetimes: false,
+                preprocessor: false,
+                keywords: JAVA_KEYWORDS,
+            },
+            "rust" => Syntax {
+                line_comment: "//",
+                block_comment: true,
+                nested_block_comments: true,
+                single_quote_is_char: true,
+                triple_quoted_strings: false,
+                template_literals: false,
+                regex_literals: false,
+                rust_raw_strings: true,
+                multiline_strings: true,
+                lifetimes: true,
+                preprocessor: false,
+                keywords: RUST_KEYWORDS,
+            },
+            _ => Syntax {
+                line_comment: "//",
+                block_comment: true,
+                nested_block_comments: false,
+                single_quote_is_char: true,
+                triple_quoted_strings: false,
+                template_literals: false,
+                regex_literals: false,
+                rust_raw_strings: false,
+                multiline_strings: false,
+                lifetimes: false,
+                preprocessor: true,
+                keywords: C_KEYWORDS,
+            },
+        }
+    }
+}
+
+// Longest operators first so that `>>=` wins over `>>` and `>`.
+const OPERATORS: &[&str] = &[
+    "===", "!==", ">>=", "<<=", "**=", "//=", "...", "..=", "::", "->", "=>", "==", "!=", "<=",
+    ">=", "&&", "||", "++", "--", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "<<", ">>", "**",
+    "//", "..", "?.", "??",
+];
+
+struct Lexer<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    column: usize,
+    syntax: Syntax,
+    language: &'a str,
+    /// Whether the next token starts an operand, where `/` opens a regex
+    /// literal rather than dividing.
+    expects_operand: bool,
+    /// Unclosed `{` inside each open `${` of a template literal, innermost
+    /// last.
+    interpolations: Vec<usize>,
+}
+
+/// Splits `code` into tokens according to the rules of `language`.
+///
+/// Whitespace is dropped; comments are kept as `TokenKind::Comment` so that
+/// callers interested in annotations can still see them.
+pub fn tokenize(code: &str, language: &str) -> Vec<Token> {
+    let mut lexer = Lexer {
+        chars: code.chars().collect(),
+        pos: 0,
+        line: 1,
+        column: 1,
+        syntax: Syntax::for_language(language),
+        language,
+        expects_operand: true,
+        interpolations: Vec::new(),
+    };
+    lexer.run()
+}
+
+impl Lexer<'_> {
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars().enumerate().all(|(i, c)| self.peek(i) == Some(c))
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.chars[..self.pos]
+            .iter()
+            .rev()
+            .take_while(|&&c| c != '\n')
+            .all(|c| c.is_whitespace())
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek(0)?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn bump_n(&mut self, n: usize) {
+        for _ in 0..n {
+            self.bump();
+        }
+    }
+
+    fn run(&mut self) -> Vec<Token> {
+        let mut tokens = Vec::new();
+
+        while let Some(c) = self.peek(0) {
+            if c.is_whitespace() {
+                self.bump();
+                continue;
+            }
+
+            let (line, column, start) = (self.line, self.column, self.pos);
+            let kind = self.scan(c);
+            let text: String = self.chars[start..self.pos].iter().collect();
+            let kind = match kind {
+                TokenKind::Identifier if self.syntax.keywords.contains(&text.as_str()) => {
+                    TokenKind::Keyword
+                }
+                other => other,
+            };
+            match kind {
+                TokenKind::Comment | TokenKind::Directive => {}
+                TokenKind::Keyword => self.expects_operand = text != "this",
+                TokenKind::Punctuation => {
+                    self.expects_operand = !matches!(text.as_str(), ")" | "]" | "}" | "++" | "--")
+                }
+                _ => self.expects_operand = false,
+            }
+
+            tokens.push(Token {
+                kind,
+                text,
+                line,
+                column,
+                end_line: self.line,
+                end_column: self.column,
+            });
+        }
+
+        tokens
+    }
+
+    fn scan(&mut self, c: char) -> TokenKind {
+        if self.starts_with(self.syntax.line_comment) {
+            while self.peek(0).is_some_and(|c| c != '\n') {
+                self.bump();
+            }
+            return TokenKind::Comment;
+        }
+
+        if self.syntax.preprocessor && c == '#' && self.at_line_start() {
+            while let Some(c) = self.peek(0) {
+                if c == '\\' && self.peek(1) == Some('\n') {
+                    self.bump_n(2);
+                } else if c == '\n' {
+                    break;
+                } else {
+                    self.bump();
+                }
+            }
+            return TokenKind::Directive;
+        }
+
+        if self.syntax.block_comment && self.starts_with("/*") {
+            self.scan_block_comment();
+            return TokenKind::Comment;
+        }
+
+        if self.syntax.regex_literals && c == '/' && self.expects_operand {
+            self.scan_regex();
+            return TokenKind::String;
+        }
+
+        if let Some(kind) = self.scan_prefixed_string() {
+            return kind;
+        }
+
+        if c.is_alphabetic() || c == '_' || c == '$' {
+            while self
+                .peek(0)
+                .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '$')
+            {
+                self.bump();
+            }
+            return TokenKind::Identifier;
+        }
+
+        if c.is_ascii_digit() {
+            self.scan_number();
+            return TokenKind::Number;
+        }
+
+        match c {
+            '"' => {
+                if self.syntax.triple_quoted_strings && self.starts_with("\"\"\"") {
+                    self.scan_triple_quoted("\"\"\"");
+                } else {
+                    self.scan_quoted('"');
+                }
+                TokenKind::String
+            }
+            '\'' if self.syntax.triple_quoted_strings && self.starts_with("'''") => {
+                self.scan_triple_quoted("'''");
+                TokenKind::String
+            }
+            '\'' if !self.syntax.single_quote_is_char => {
+                self.scan_quoted('\'');
+                TokenKind::String
+            }
+            '\'' => self.scan_char_or_lifetime(),
+            '`' if self.syntax.template_literals => {
+                self.scan_template();
+                TokenKind::String
+            }
+            '{' if !self.interpolations.is_empty() => {
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.bump();
+                TokenKind::Punctuation
+            }
+            '}' if self.interpolations.last() == Some(&0) => {
+                // End of `${...}`: the rest of the template follows
+                self.interpolations.pop();
+                self.scan_template();
+                TokenKind::String
+            }
+            '}' if !self.interpolations.is_empty() => {
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth -= 1;
+                }
+                self.bump();
+                TokenKind::Punctuation
+            }
+            _ => {
+                let operator = OPERATORS
+                    .iter()
+                    .find(|op| self.starts_with(op))
+                    .map(|op| op.chars().count());
+                self.bump_n(operator.unwrap_or(1));
+                TokenKind::Punctuation
+            }
+        }
+    }
+
+    fn scan_block_comment(&mut self) {
+        self.bump_n(2);
+        let mut depth = 1;
+        while self.peek(0).is_some() {
+            if self.starts_with("*/") {
+                self.bump_n(2);
+                depth -= 1;
+                if depth == 0 {
+                    return;
+                }
+            } else if self.syntax.nested_block_comments && self.starts_with("/*") {
+                self.bump_n(2);
+                depth += 1;
+            } else {
+                self.bump();
+            }
+        }
+    }
+
+    fn scan_number(&mut self) {
+        while let Some(c) = self.peek(0) {
+            let is_fraction = c == '.' && self.peek(1).is_some_and(|n| n.is_ascii_digit());
+            if c.is_alphanumeric() || c == '_' || is_fraction {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn scan_quoted(&mut self, quote: char) {
+        self.bump();
+        while let Some(c) = self.bump() {
+            if c == '\\' {
+                self.bump();
+            } else if c == quote {
+                return;
+            } else if c == '\n' && quote != '`' && !self.syntax.multiline_strings {
+                // Unterminated literal: stop at the end of the line
+                return;
+            }
+        }
+    }
+
+    /// Scans template text from the opening backquote, or from the `}`
+    /// closing an interpolation, up to the closing backquote or the next
+    /// `${`. The code inside `${...}` is lexed as ordinary tokens.
+    fn scan_template(&mut self) {
+        self.bump();
+        while let Some(c) = self.bump() {
+            if c == '\\' {
+                self.bump();
+            } else if c == '`' {
+                return;
+            } else if c == '$' && self.peek(0) == Some('{') {
+                self.bump();
+                self.interpolations.push(0);
+                return;
+            }
+        }
+    }
+
+    /// Scans a JavaScript regex literal such as `/[/}]+/g`, flags included.
+    fn scan_regex(&mut self) {
+        self.bump();
+        let mut in_class = false;
+        while let Some(c) = self.peek(0) {
+            match c {
+                '\\' => {
+                    self.bump();
+                }
+                '[' => in_class = true,
+                ']' => in_class = false,
+                '/' if !in_class => break,
+                // Unterminated literal: stop at the end of the line
+                '\n' => return,
+                _ => {}
+            }
+            self.bump();
+        }
+        self.bump();
+        while self.peek(0).is_some_and(|c| c.is_alphanumeric()) {
+            self.bump();
+        }
+    }
+
+    fn scan_triple_quoted(&mut self, delimiter: &str) {
+        self.bump_n(3);
+        while self.peek(0).is_some() {
+            if self.peek(0) == Some('\\') {
+                self.bump_n(2);
+            } else if self.starts_with(delimiter) {
+                self.bump_n(3);
+                return;
+            } else {
+                self.bump();
+            }
+        }
+    }
+
+    fn scan_raw_until(&mut self, terminator: &str) {
+        while self.peek(0).is_some() {
+            if self.starts_with(terminator) {
+                self.bump_n(terminator.chars().count());
+                return;
+            }
+            self.bump();
+        }
+    }
+
+    /// Handles string literals introduced by a prefix: Python `r"..."`,
+    /// `f'...'`, `b"""..."""`, Rust `r#"..."#` / `b"..."`, C++ `R"(...)"`.
+    fn scan_prefixed_string(&mut self) -> Option<TokenKind> {
+        let mut prefix_len = 0;
+        while prefix_len < 3
+            && self
+                .peek(prefix_len)
+                .is_some_and(|c| matches!(c.to_ascii_lowercase(), 'r' | 'b' | 'f' | 'u'))
+        {
+            prefix_len += 1;
+        }
+        if prefix_len == 0 {
+            return None;
+        }
+        // A prefix must not be the tail of a longer identifier (`for"` is not
+        // valid anyway, but `bar"` must stay an identifier followed by a string).
+        if self.pos > 0 {
+            let prev = self.chars[self.pos - 1];
+            if prev.is_alphanumeric() || prev == '_' {
+                return None;
+            }
+        }
+
+        let prefix: String = self.chars[self.pos..self.pos + prefix_len].iter().collect();
+        let quote = self.peek(prefix_len)?;
+
+        if self.syntax.rust_raw_strings && prefix.contains('r') && matches!(quote, '"' | '#') {
+            let mut hashes = 0;
+            while self.peek(prefix_len + hashes) == Some('#') {
+                hashes += 1;
+            }
+            if self.peek(prefix_len + hashes) != Some('"') {
+                return None;
+            }
+            self.bump_n(prefix_len + hashes + 1);
+            let terminator = format!("\"{}", "#".repeat(hashes));
+            self.scan_raw_until(&terminator);
+            return Some(TokenKind::String);
+        }
+
+        match self.language.to_lowercase().as_str() {
+            "python" if matches!(quote, '"' | '\'') => {
+                self.bump_n(prefix_len);
+                let triple: String = std::iter::repeat_n(quote, 3).collect();
+                if self.starts_with(&triple) {
+                    self.scan_triple_quoted(&triple);
+                } else {
+                    self.scan_quoted(quote);
+                }
+                Some(TokenKind::String)
+            }
+            "rust" if prefix == "b" && quote == '"' => {
+                self.bump_n(prefix_len);
+                self.scan_quoted('"');
+                Some(TokenKind::String)
+            }
+            "rust" if prefix == "b" && quote == '\'' => {
+                self.bump_n(prefix_len);
+                self.scan_quoted('\'');
+                Some(TokenKind::Char)
+            }
+            "cpp" | "c++" | "c" if prefix.ends_with('R') && quote == '"' => {
+                self.bump_n(prefix_len + 1);
+                let mut delimiter = String::new();
+                while let Some(c) = self.peek(0) {
+                    self.bump();
+                    if c == '(' {
+                        break;
+                    }
+                    delimiter.push(c);
+                }
+                self.scan_raw_until(&format!("){}\"", delimiter));
+                Some(TokenKind::String)
+            }
+            _ => None,
+        }
+    }
+
+    /// Distinguishes `'x'` / `'\n'` char literals from Rust lifetimes and
+    /// loop labels such as `'a` or `'outer`.
+    fn scan_char_or_lifetime(&mut self) -> TokenKind {
+        let is_char = match (self.peek(1), self.peek(2)) {
+            (Some('\\'), _) => true,
+            (Some(_), Some('\'')) => true,
+            _ => !self.syntax.lifetimes,
+        };
+
+        if is_char {
+            self.scan_quoted('\'');
+            TokenKind::Char
+        } else {
+            self.bump();
+            while self
+                .peek(0)
+                .is_some_and(|c| c.is_alphanumeric() || c == '_')
+            {
+                self.bump();
+            }
+            TokenKind::Identifier
+        }
+    }
+}
+
+/// Returns true when the identifier at `index` is directly followed by `(`.
+pub fn is_call(tokens: &[Token], index: usize) -> bool {
+    tokens[index].kind == TokenKind::Identifier
+        && tokens.get(index + 1).is_some_and(|t| t.is_punct("("))
+}
+
+/// Returns the index of the token closing the bracket opened at `open`.
+pub fn matching_close(tokens: &[Token], open: usize) -> Option<usize> {
+    let close = match tokens[open].text.as_str() {
+        "(" => ")",
+        "[" => "]",
+        "{" => "}",
+        _ => return None,
+    };
+    let open_text = tokens[open].text.as_str();
+    let mut depth = 0usize;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        if token.is_punct(open_text) {
+            depth += 1;
+        } else if token.is_punct(close) {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
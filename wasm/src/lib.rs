@@ -1,107 +1,361 @@
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use wasm_bindgen::prelude::*;
-
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(js_namespace = console)]
-    fn log(s: &str);
-}
+//! Estimates the time and space complexity of source code.
+//!
+//! [`ComplexityAnalyzer`] analyzes one file and [`analyze_sources`] a set of
+//! files whose functions call each other:
+//!
+//! ```
+//! use big_o_analyser::ComplexityAnalyzer;
+//!
+//! let code = "def total(xs):\n    s = 0\n    for x in xs:\n        s += x\n    return s\n";
+//! let result = ComplexityAnalyzer::new("python").analyze(code);
+//! assert_eq!(result.functions()[0].complexity().to_string(), "O(n)");
+//! ```
+//!
+//! The JavaScript bindings used by the VS Code extension are built with the
+//! `wasm` feature, on by default. Native tools can depend on the crate with
+//! `default-features = false`.
 
-macro_rules! console_log {
-    ($($t:tt)*) => {
-        log(&format_args!($($t)*).to_string())
-    }
-}
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+mod annotations;
+mod baseline;
+mod blocks;
+mod budget;
+mod calls;
+mod cases;
+mod complexity;
+mod cost;
+mod diff;
+mod lexer;
+mod loops;
+mod memo;
+mod project;
+mod recurrence;
+mod sarif;
+mod space;
+mod tables;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+pub use baseline::{Baseline, BaselineReport, Regression};
+use blocks::{Block, BlockKind};
+pub use budget::{Budget, BudgetReport, Violation};
+pub use cases::Cases;
+pub use complexity::{Base, Complexity, ParseComplexityError};
+pub use cost::CostNode;
+pub use diff::{ComplexityDiff, FunctionChange, diff_analyses};
+use lexer::{Token, TokenKind};
+pub use project::{
+    FileAnalysis, ProjectAnalysis, ProjectSummary, RankedFunction, SourceFile, analyze_sources,
+};
+pub use sarif::{SarifLog, to_sarif};
+pub use space::SpaceAnalysis;
+#[cfg(feature = "wasm")]
+pub use wasm::{
+    analyze_complexity, analyze_project, diff_complexity, get_supported_languages, init,
+};
+
+/// Members of a recursion group named in its warning; the rest are counted.
+const NAMED_GROUP_MEMBERS: usize = 5;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComplexityResult {
-    complexity: String,
+    complexity: Complexity,
     confidence: f64,
     details: Vec<String>,
     line_start: usize,
     line_end: usize,
+    parameters: Vec<String>,
+    variables: BTreeMap<String, String>,
+    cost_tree: CostNode,
+    space_complexity: SpaceAnalysis,
+    cases: Cases,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FunctionAnalysis {
     function: String,
-    complexity: String,
+    /// Parameter names, in order.
+    parameters: Vec<String>,
+    complexity: Complexity,
     confidence: f64,
     details: Vec<String>,
     line_start: usize,
     line_end: usize,
+    /// What each variable in `complexity` and `space_complexity` stands for,
+    /// e.g. `n` -> `len(rows)`.
+    variables: BTreeMap<String, String>,
+    /// Cost of each loop and the blocks containing them.
+    cost_tree: CostNode,
+    /// Memory used on top of the input, with its own confidence and details.
+    space_complexity: SpaceAnalysis,
+    /// Best, average and worst case; `complexity` is the average.
+    cases: Cases,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AnalysisResult {
-    overall: String,
+    overall: Complexity,
+    overall_space: Complexity,
     functions: Vec<FunctionAnalysis>,
     language: String,
     warnings: Vec<String>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum Complexity {
-    Constant,     // O(1)
-    Logarithmic,  // O(log n)
-    Linear,       // O(n)
-    Linearithmic, // O(n log n)
-    Quadratic,    // O(n²)
-    Cubic,        // O(n³)
-    Polynomial,   // O(n^k) where k > 3
-    Exponential,  // O(2ⁿ)
-    Factorial,    // O(n!)
+impl ComplexityResult {
+    pub fn complexity(&self) -> &Complexity {
+        &self.complexity
+    }
+
+    pub fn confidence(&self) -> f64 {
+        self.confidence
+    }
+
+    pub fn details(&self) -> &[String] {
+        &self.details
+    }
+
+    pub fn line_start(&self) -> usize {
+        self.line_start
+    }
+
+    pub fn line_end(&self) -> usize {
+        self.line_end
+    }
+
+    pub fn parameters(&self) -> &[String] {
+        &self.parameters
+    }
+
+    pub fn variables(&self) -> &BTreeMap<String, String> {
+        &self.variables
+    }
+
+    pub fn cost_tree(&self) -> &CostNode {
+        &self.cost_tree
+    }
+
+    pub fn space_complexity(&self) -> &SpaceAnalysis {
+        &self.space_complexity
+    }
+
+    pub fn cases(&self) -> &Cases {
+        &self.cases
+    }
 }
 
-impl Complexity {
-    fn to_string(&self) -> &'static str {
-        match self {
-            Complexity::Constant => "O(1)",
-            Complexity::Logarithmic => "O(log n)",
-            Complexity::Linear => "O(n)",
-            Complexity::Linearithmic => "O(n log n)",
-            Complexity::Quadratic => "O(n²)",
-            Complexity::Cubic => "O(n³)",
-            Complexity::Polynomial => "O(n^k)",
-            Complexity::Exponential => "O(2ⁿ)",
-            Complexity::Factorial => "O(n!)",
-        }
+impl FunctionAnalysis {
+    /// Name of the function, or `main` for code outside any function.
+    pub fn function(&self) -> &str {
+        &self.function
     }
 
-    fn order(&self) -> u8 {
-        match self {
-            Complexity::Constant => 0,
-            Complexity::Logarithmic => 1,
-            Complexity::Linear => 2,
-            Complexity::Linearithmic => 3,
-            Complexity::Quadratic => 4,
-            Complexity::Cubic => 5,
-            Complexity::Polynomial => 6,
-            Complexity::Exponential => 7,
-            Complexity::Factorial => 8,
-        }
+    /// Parameter names, in order.
+    pub fn parameters(&self) -> &[String] {
+        &self.parameters
     }
 
-    fn max(self, other: Complexity) -> Complexity {
-        if self.order() > other.order() {
-            self
-        } else {
-            other
-        }
+    /// Average-case running time.
+    pub fn complexity(&self) -> &Complexity {
+        &self.complexity
+    }
+
+    /// How reliable `complexity` is, from 0 to 1.
+    pub fn confidence(&self) -> f64 {
+        self.confidence
+    }
+
+    /// Patterns found in the function and how they were costed.
+    pub fn details(&self) -> &[String] {
+        &self.details
+    }
+
+    pub fn line_start(&self) -> usize {
+        self.line_start
+    }
+
+    pub fn line_end(&self) -> usize {
+        self.line_end
+    }
+
+    /// What each variable in the complexities stands for.
+    pub fn variables(&self) -> &BTreeMap<String, String> {
+        &self.variables
+    }
+
+    pub fn cost_tree(&self) -> &CostNode {
+        &self.cost_tree
+    }
+
+    pub fn space_complexity(&self) -> &SpaceAnalysis {
+        &self.space_complexity
+    }
+
+    pub fn cases(&self) -> &Cases {
+        &self.cases
+    }
+}
+
+impl AnalysisResult {
+    /// Highest complexity of any function.
+    pub fn overall(&self) -> &Complexity {
+        &self.overall
+    }
+
+    /// Highest space complexity of any function.
+    pub fn overall_space(&self) -> &Complexity {
+        &self.overall_space
+    }
+
+    /// Analyses of the functions, in source order.
+    pub fn functions(&self) -> &[FunctionAnalysis] {
+        &self.functions
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Limitations of the analysis, such as code too large to analyze.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
     }
 }
 
 #[derive(Debug)]
-pub struct FunctionInfo {
+pub(crate) struct FunctionInfo {
     name: String,
     start_line: usize,
     end_line: usize,
-    loop_depth: usize,
-    recursive_calls: usize,
-    has_binary_search: bool,
-    has_sorting: bool,
-    has_dynamic_programming: bool,
+    block: Block,
+    /// What comments in or above the function ask of its analysis.
+    annotations: annotations::Annotations,
+}
+
+/// The analyzer language for a file, from its extension.
+pub fn language_for_path(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    Some(match extension.as_str() {
+        "js" | "jsx" | "mjs" | "cjs" => "javascript",
+        "ts" | "tsx" | "mts" | "cts" => "typescript",
+        "py" | "pyw" => "python",
+        "java" => "java",
+        "c" | "h" => "c",
+        "cpp" | "cc" | "cxx" | "hpp" | "hh" | "hxx" => "cpp",
+        "rs" => "rust",
+        _ => return None,
+    })
+}
+
+/// One source file, tokenized and split into functions.
+struct Unit<'a> {
+    analyzer: &'a ComplexityAnalyzer,
+    tokens: Vec<Token>,
+    functions: Vec<FunctionInfo>,
+    warnings: Vec<String>,
+}
+
+/// Analyzes the functions of all `units` together, so that a call into
+/// another unit is charged what the callee costs. `modules` says which
+/// units each one imports. Returns the analyses of each unit's functions.
+fn analyze_units(
+    units: &mut [Unit],
+    modules: &[calls::Module],
+) -> Vec<Vec<Option<ComplexityResult>>> {
+    let nodes: Vec<calls::Node> = units
+        .iter()
+        .enumerate()
+        .flat_map(|(file, unit)| {
+            unit.functions.iter().map(move |function| calls::Node {
+                file,
+                tokens: &unit.tokens,
+                function,
+            })
+        })
+        .collect();
+
+    // Callees first, so each call can be charged what the callee costs.
+    // Functions calling each other are analyzed as one recursion group.
+    let graph = calls::CallGraph::build(&nodes, modules);
+    let mut summaries: Vec<Option<calls::Summary>> = vec![None; nodes.len()];
+    let mut analyses: Vec<Option<ComplexityResult>> = vec![None; nodes.len()];
+    let mut warnings: Vec<(usize, String)> = Vec::new();
+    for component in graph.components() {
+        let group: Vec<&str> = component
+            .iter()
+            .map(|&position| nodes[position].function.name.as_str())
+            .collect();
+        if group.len() > 1 {
+            let members = if group.len() > NAMED_GROUP_MEMBERS {
+                format!(
+                    "{}, … and {} more",
+                    group[..NAMED_GROUP_MEMBERS].join(", "),
+                    group.len() - NAMED_GROUP_MEMBERS
+                )
+            } else {
+                group.join(", ")
+            };
+            warnings.push((
+                nodes[component[0]].file,
+                format!(
+                    "Mutual recursion between {}: analyzed as one recursion group",
+                    members
+                ),
+            ));
+        }
+        let mut analyzed = Vec::with_capacity(component.len());
+        for &position in &component {
+            let node = &nodes[position];
+            // Ignored functions are left out, and their callers not charged
+            if node.function.annotations.ignore {
+                analyzed.push(None);
+                continue;
+            }
+            let sites: Vec<calls::CallSite> = graph
+                .sites(position)
+                .iter()
+                .filter(|site| !component.contains(&site.callee))
+                .cloned()
+                .collect();
+            let (analysis, summary) = units[node.file].analyzer.analyze_function(
+                node.function,
+                node.tokens,
+                &group,
+                &sites,
+                &summaries,
+            );
+            if let Some((expected, line)) = &node.function.annotations.expect
+                && analysis.complexity.order(expected).is_ne()
+            {
+                warnings.push((
+                    node.file,
+                    format!(
+                        "'{}' is {}, but the comment at line {} expects {}",
+                        node.function.name, analysis.complexity, line, expected
+                    ),
+                ));
+            }
+            analyzed.push(Some((analysis, summary)));
+        }
+        for (&position, analyzed) in component.iter().zip(analyzed) {
+            if let Some((analysis, summary)) = analyzed {
+                summaries[position] = Some(summary);
+                analyses[position] = Some(analysis);
+            }
+        }
+    }
+
+    let files: Vec<usize> = nodes.iter().map(|node| node.file).collect();
+    for (file, warning) in warnings {
+        units[file].warnings.push(warning);
+    }
+    let mut per_unit: Vec<Vec<Option<ComplexityResult>>> = vec![Vec::new(); units.len()];
+    for (file, analysis) in files.into_iter().zip(analyses) {
+        per_unit[file].push(analysis);
+    }
+    per_unit
 }
 
 pub struct ComplexityAnalyzer {
@@ -116,24 +370,24 @@ impl ComplexityAnalyzer {
         // Add language-specific builtin function complexities
         match language.to_lowercase().as_str() {
             "javascript" | "typescript" => {
-                builtin_functions.insert("sort", Complexity::Linearithmic);
-                builtin_functions.insert("indexOf", Complexity::Linear);
-                builtin_functions.insert("includes", Complexity::Linear);
-                builtin_functions.insert("find", Complexity::Linear);
-                builtin_functions.insert("filter", Complexity::Linear);
-                builtin_functions.insert("map", Complexity::Linear);
-                builtin_functions.insert("reduce", Complexity::Linear);
+                builtin_functions.insert("sort", Complexity::linearithmic());
+                builtin_functions.insert("indexOf", Complexity::linear());
+                builtin_functions.insert("includes", Complexity::linear());
+                builtin_functions.insert("find", Complexity::linear());
+                builtin_functions.insert("filter", Complexity::linear());
+                builtin_functions.insert("map", Complexity::linear());
+                builtin_functions.insert("reduce", Complexity::linear());
             }
             "python" => {
-                builtin_functions.insert("sorted", Complexity::Linearithmic);
-                builtin_functions.insert("sort", Complexity::Linearithmic);
-                builtin_functions.insert("max", Complexity::Linear);
-                builtin_functions.insert("min", Complexity::Linear);
-                builtin_functions.insert("sum", Complexity::Linear);
+                builtin_functions.insert("sorted", Complexity::linearithmic());
+                builtin_functions.insert("sort", Complexity::linearithmic());
+                builtin_functions.insert("max", Complexity::linear());
+                builtin_functions.insert("min", Complexity::linear());
+                builtin_functions.insert("sum", Complexity::linear());
             }
             "java" => {
-                builtin_functions.insert("Arrays.sort", Complexity::Linearithmic);
-                builtin_functions.insert("Collections.sort", Complexity::Linearithmic);
+                builtin_functions.insert("Arrays.sort", Complexity::linearithmic());
+                builtin_functions.insert("Collections.sort", Complexity::linearithmic());
             }
             _ => {}
         }
@@ -145,599 +399,589 @@ impl ComplexityAnalyzer {
     }
 
     pub fn analyze(&self, code: &str) -> AnalysisResult {
+        let Some(mut unit) = self.unit(code) else {
+            return self.too_large();
+        };
+        let mut analyses =
+            analyze_units(std::slice::from_mut(&mut unit), &[calls::Module::default()]);
+        self.report(unit, analyses.remove(0))
+    }
+
+    /// Result for code too large to analyze.
+    fn too_large(&self) -> AnalysisResult {
+        AnalysisResult {
+            overall: Complexity::constant(),
+            overall_space: Complexity::constant(),
+            functions: vec![],
+            language: self.language.clone(),
+            warnings: vec!["Code too large to analyze safely".to_string()],
+        }
+    }
+
+    /// Tokenizes `code` and extracts its functions, or `None` when the code
+    /// is too large to analyze.
+    fn unit(&self, code: &str) -> Option<Unit<'_>> {
         // Early validation to prevent processing huge inputs
         if code.len() > 100_000 {
-            return AnalysisResult {
-                overall: "O(1)".to_string(),
-                functions: vec![],
-                language: self.language.clone(),
-                warnings: vec!["Code too large to analyze safely".to_string()],
-            };
+            return None;
         }
 
-        let functions = self.extract_functions(code);
-        let mut function_results = Vec::with_capacity(functions.len());
+        let all_tokens = lexer::tokenize(code, &self.language);
+        let tokens: Vec<Token> = all_tokens
+            .iter()
+            .filter(|t| !t.is_trivia())
+            .cloned()
+            .collect();
+        let mut functions = self.extract_functions(code, &tokens);
         let mut warnings = Vec::new();
 
         if functions.is_empty() {
             warnings
                 .push("No functions detected. Analyzing entire code as single block.".to_string());
+            let tree = blocks::build_block_tree(&tokens, &self.language);
+            let line_count = code.lines().count().max(1);
+            functions.push(self.create_function_info("main".to_string(), 1, line_count, tree));
         }
+        annotations::attach(&all_tokens, &tokens, &mut functions, &mut warnings);
+
+        Some(Unit {
+            analyzer: self,
+            tokens,
+            functions,
+            warnings,
+        })
+    }
+
+    /// Collects the analyses of the functions of `unit`, in source order.
+    fn report(&self, unit: Unit, analyses: Vec<Option<ComplexityResult>>) -> AnalysisResult {
+        let mut function_results = Vec::with_capacity(unit.functions.len());
 
-        for func in functions {
-            let analysis = self.analyze_function(&func, code);
+        for (func, analysis) in unit.functions.into_iter().zip(analyses) {
+            let Some(analysis) = analysis else {
+                continue;
+            };
             function_results.push(FunctionAnalysis {
                 function: func.name,
+                parameters: analysis.parameters,
                 complexity: analysis.complexity,
                 confidence: analysis.confidence,
                 details: analysis.details,
                 line_start: analysis.line_start,
                 line_end: analysis.line_end,
+                variables: analysis.variables,
+                cost_tree: analysis.cost_tree,
+                space_complexity: analysis.space_complexity,
+                cases: analysis.cases,
             });
         }
 
         let overall = self.get_overall_complexity(&function_results);
+        let overall_space = function_results
+            .iter()
+            .map(|f| f.space_complexity.complexity().clone())
+            .max()
+            .unwrap_or_else(Complexity::constant);
 
         AnalysisResult {
             overall,
+            overall_space,
             functions: function_results,
             language: self.language.clone(),
-            warnings,
+            warnings: unit.warnings,
         }
     }
 
-    fn extract_functions(&self, code: &str) -> Vec<FunctionInfo> {
-        let mut functions = Vec::new();
-        let lines: Vec<&str> = code.lines().collect();
+    fn extract_functions(&self, code: &str, tokens: &[Token]) -> Vec<FunctionInfo> {
+        let line_count = code.lines().count();
 
         // Limit processing to reasonable number of lines
-        if lines.len() > 10_000 {
+        if line_count > 10_000 {
+            let scope = tokens.iter().take_while(|t| t.line <= 1000).count();
+            let tree = blocks::build_block_tree(&tokens[..scope], &self.language);
             return vec![self.create_function_info(
                 "main".to_string(),
                 1,
-                lines.len().min(1000), // Limit analysis scope
+                line_count.min(1000), // Limit analysis scope
+                tree,
             )];
         }
 
-        match self.language.to_lowercase().as_str() {
-            "python" => self.extract_python_functions(&lines, &mut functions),
-            "javascript" | "typescript" => self.extract_js_functions(&lines, &mut functions),
-            "java" | "c" | "cpp" | "c++" | "rust" => {
-                self.extract_c_style_functions(&lines, &mut functions)
-            }
-            _ => self.extract_generic_functions(&lines, &mut functions),
-        }
-
-        if functions.is_empty() {
-            functions.push(self.create_function_info("main".to_string(), 1, lines.len()));
-        }
+        let tree = blocks::build_block_tree(tokens, &self.language);
+        let functions: Vec<FunctionInfo> = tree
+            .functions()
+            .into_iter()
+            .filter_map(|block| match &block.kind {
+                BlockKind::Function(name) if name.len() < 100 => Some(self.create_function_info(
+                    name.clone(),
+                    block.start_line,
+                    block.end_line,
+                    block.clone(),
+                )),
+                _ => None,
+            })
+            .collect();
 
         functions
     }
 
-    fn extract_python_functions(&self, lines: &[&str], functions: &mut Vec<FunctionInfo>) {
-        let mut current_function: Option<(String, usize, usize)> = None;
-
-        for (i, line) in lines.iter().enumerate() {
-            let indent_level = line.len() - line.trim_start().len();
-
-            if line.trim_start().starts_with("def ") {
-                // Save previous function
-                if let Some((name, start, _)) = current_function.take() {
-                    functions.push(self.create_function_info(name, start, i));
-                }
-
-                if let Some(func_name) = self.extract_python_function_name(line) {
-                    current_function = Some((func_name, i + 1, indent_level));
-                }
-            } else if let Some((name, start, base_indent)) = current_function.as_ref() {
-                // Function ended if we're back to base level or less
-                if !line.trim().is_empty() && indent_level <= *base_indent {
-                    functions.push(self.create_function_info(name.clone(), *start, i));
-                    current_function = None;
-
-                    // Check if this line starts a new function
-                    if line.trim_start().starts_with("def ") {
-                        if let Some(func_name) = self.extract_python_function_name(line) {
-                            current_function = Some((func_name, i + 1, indent_level));
-                        }
-                    }
-                }
-            }
-        }
-
-        // Handle last function
-        if let Some((name, start, _)) = current_function {
-            functions.push(self.create_function_info(name, start, lines.len()));
-        }
-    }
-
-    fn extract_js_functions(&self, lines: &[&str], functions: &mut Vec<FunctionInfo>) {
-        let mut current_function: Option<(String, usize)> = None;
-        let mut brace_count = 0;
-
-        for (i, line) in lines.iter().enumerate() {
-            let trimmed = line.trim();
-
-            if let Some(func_name) = self.extract_js_function_name(trimmed) {
-                if let Some((name, start)) = current_function.take() {
-                    functions.push(self.create_function_info(name, start, i));
-                }
-
-                current_function = Some((func_name, i + 1));
-                brace_count = line.matches('{').count() as i32 - line.matches('}').count() as i32;
-            } else if current_function.is_some() {
-                brace_count += line.matches('{').count() as i32 - line.matches('}').count() as i32;
-
-                if brace_count <= 0 {
-                    let (name, start) = current_function.take().unwrap();
-                    functions.push(self.create_function_info(name, start, i + 1));
-                }
-            }
-        }
-
-        if let Some((name, start)) = current_function {
-            functions.push(self.create_function_info(name, start, lines.len()));
-        }
-    }
-
-    fn extract_c_style_functions(&self, lines: &[&str], functions: &mut Vec<FunctionInfo>) {
-        let mut current_function: Option<(String, usize)> = None;
-        let mut brace_count = 0;
-
-        for (i, line) in lines.iter().enumerate() {
-            let trimmed = line.trim();
-
-            if let Some(func_name) = self.extract_c_style_function_name(trimmed) {
-                if let Some((name, start)) = current_function.take() {
-                    functions.push(self.create_function_info(name, start, i));
-                }
-
-                current_function = Some((func_name, i + 1));
-                brace_count = line.matches('{').count() as i32 - line.matches('}').count() as i32;
-            } else if current_function.is_some() {
-                brace_count += line.matches('{').count() as i32 - line.matches('}').count() as i32;
-
-                if brace_count <= 0 {
-                    let (name, start) = current_function.take().unwrap();
-                    functions.push(self.create_function_info(name, start, i + 1));
-                }
-            }
-        }
-
-        if let Some((name, start)) = current_function {
-            functions.push(self.create_function_info(name, start, lines.len()));
-        }
-    }
-
-    fn extract_generic_functions(&self, lines: &[&str], functions: &mut Vec<FunctionInfo>) {
-        self.extract_c_style_functions(lines, functions);
-    }
-
-    fn extract_python_function_name(&self, line: &str) -> Option<String> {
-        if let Some(paren_pos) = line.find('(') {
-            let after_def = line.trim_start().strip_prefix("def ")?;
-            let name_end = paren_pos.saturating_sub(4);
-            let name = after_def.get(..name_end)?.trim();
-            if !name.is_empty() && name.len() < 100 {
-                // Reasonable name length
-                return Some(name.to_string());
-            }
-        }
-        None
-    }
-
-    fn extract_js_function_name(&self, line: &str) -> Option<String> {
-        // Regular function declaration
-        if line.starts_with("function ") {
-            if let Some(paren_pos) = line.find('(') {
-                let name = line.get(9..paren_pos)?.trim();
-                if !name.is_empty() && name.len() < 100 {
-                    return Some(name.to_string());
-                }
-            }
-        }
-
-        // Arrow function with const/let/var
-        if let Some(arrow_pos) = line.find(" => ") {
-            if let Some(eq_pos) = line.find(" = ") {
-                if eq_pos < arrow_pos {
-                    let before_eq = line.get(..eq_pos)?;
-                    if let Some(space_pos) = before_eq.rfind(' ') {
-                        let name = before_eq.get(space_pos + 1..)?.trim();
-                        if !name.is_empty() && name.len() < 100 {
-                            return Some(name.to_string());
-                        }
-                    }
-                }
-            }
-        }
-
-        // Method definition
-        if line.contains(':') && line.contains("function") {
-            if let Some(colon_pos) = line.find(':') {
-                let name = line.get(..colon_pos)?.trim();
-                if !name.is_empty()
-                    && name.len() < 100
-                    && name.chars().all(|c| c.is_alphanumeric() || c == '_')
-                {
-                    return Some(name.to_string());
-                }
-            }
-        }
-
-        None
-    }
-
-    fn extract_c_style_function_name(&self, line: &str) -> Option<String> {
-        if line.contains('(') && (line.contains('{') || line.ends_with(';')) {
-            let before_paren = line.split('(').next()?;
-            let words: Vec<&str> = before_paren.split_whitespace().collect();
-            if let Some(last_word) = words.last() {
-                // Skip common keywords
-                if !matches!(*last_word, "if" | "while" | "for" | "switch" | "catch")
-                    && last_word.len() < 100
-                {
-                    return Some(last_word.to_string());
-                }
-            }
-        }
-        None
-    }
-
-    fn create_function_info(&self, name: String, start: usize, end: usize) -> FunctionInfo {
+    fn create_function_info(
+        &self,
+        name: String,
+        start: usize,
+        end: usize,
+        block: Block,
+    ) -> FunctionInfo {
         FunctionInfo {
-            loop_depth: 0,                  // Will be calculated during analysis
-            recursive_calls: 0,             // Will be calculated during analysis
-            has_binary_search: false,       // Will be calculated during analysis
-            has_sorting: false,             // Will be calculated during analysis
-            has_dynamic_programming: false, // Will be calculated during analysis
             name,
             start_line: start,
             end_line: end,
+            block,
+            annotations: annotations::Annotations::default(),
         }
     }
 
-    fn analyze_function(&self, func: &FunctionInfo, full_code: &str) -> ComplexityResult {
-        let mut complexity = Complexity::Constant;
+    /// Analyzes one function. `group` lists the functions it is mutually
+    /// recursive with, itself included; `sites` are its calls to functions
+    /// outside the group and `summaries` what is known about them so far.
+    fn analyze_function(
+        &self,
+        func: &FunctionInfo,
+        tokens: &[Token],
+        group: &[&str],
+        sites: &[calls::CallSite],
+        summaries: &[Option<calls::Summary>],
+    ) -> (ComplexityResult, calls::Summary) {
+        let mut complexity = Complexity::constant();
         let mut confidence = 0.9f64;
         let mut details = Vec::new();
-
-        // Get function body slice safely
-        let lines: Vec<&str> = full_code.lines().collect();
-        let start_idx = func.start_line.saturating_sub(1);
-        let end_idx = func.end_line.min(lines.len());
-
-        if start_idx >= lines.len() || start_idx >= end_idx {
+        let language = self.language.to_lowercase();
+        let signature =
+            &tokens[func.block.span.start..func.block.header.end.max(func.block.span.start)];
+        let parameters = loops::parameters(signature);
+
+        // Select the tokens of the function, leaving out nested functions
+        let body: Vec<Token> = func
+            .block
+            .own_token_indices()
+            .into_iter()
+            .map(|i| tokens[i].clone())
+            .collect();
+
+        if body.is_empty() {
             details.push("Unable to analyze function body".to_string());
-            return ComplexityResult {
-                complexity: complexity.to_string().to_string(),
+            let cases = Cases::new(&complexity);
+            let summary = calls::Summary {
+                complexity: complexity.clone(),
+                parameters: parameters.clone(),
+                sources: Vec::new(),
+                chain: vec![func.name.clone()],
+            };
+            let analysis = ComplexityResult {
+                complexity,
                 confidence,
                 details,
                 line_start: func.start_line,
                 line_end: func.end_line,
+                parameters,
+                variables: BTreeMap::new(),
+                cost_tree: cost::cost_tree(
+                    tokens,
+                    &func.block,
+                    &mut loops::Symbols::default(),
+                    &language,
+                    &mut Vec::new(),
+                ),
+                space_complexity: space::space(
+                    tokens,
+                    &func.block,
+                    &mut loops::Symbols::default(),
+                    &language,
+                    None,
+                    &[],
+                ),
+                cases,
             };
+            return (analysis, summary);
         }
 
-        let function_body = lines[start_idx..end_idx].join("\n");
+        // Calls inside the signature are the declaration itself
+        let signature_len = func.block.header.end.saturating_sub(func.block.span.start);
+        let statements = &body[signature_len.min(body.len())..];
 
         // Check for builtin function calls
         for (builtin, builtin_complexity) in &self.builtin_functions {
-            if function_body.contains(builtin) {
+            if self.calls_builtin(statements, builtin) {
                 complexity = complexity.max(builtin_complexity.clone());
                 details.push(format!("Built-in function '{}' detected", builtin));
             }
         }
 
         // Calculate properties
-        let loop_depth = self.calculate_loop_depth(&function_body);
-        let recursive_calls = self.count_function_calls(&function_body, &func.name);
-        let has_binary_search = self.detect_binary_search(&function_body);
-        let has_sorting = self.detect_sorting(&function_body);
-        let has_dynamic_programming = self.detect_dynamic_programming(&function_body);
+        let loop_depth = func.block.loop_depth().min(10); // Cap at reasonable depth
+        let mut symbols = loops::Symbols::with_aliases(&body);
+        symbols.assume(&func.annotations.assumptions);
+        loops::assign_symbols(tokens, &func.block, &mut symbols);
+        let cost_tree = cost::cost_tree(tokens, &func.block, &mut symbols, &language, &mut details);
+        let loop_cost = cost_tree.cost().clone();
+        let recursive_calls: usize = group
+            .iter()
+            .map(|name| self.count_function_calls(statements, name))
+            .sum();
+        let has_binary_search = self.detect_binary_search(&body);
+        let has_sorting = self.detect_sorting(statements);
+        let tables = tables::tables(tokens, &func.block, &mut symbols);
+        let call_cost = calls::call_cost(
+            tokens,
+            &func.name,
+            &func.block,
+            sites,
+            summaries,
+            &mut symbols,
+        );
 
         // Analyze loop complexity
         match loop_depth {
-            0 => {
-                if recursive_calls == 0 {
-                    details.push("No loops or recursion detected".to_string());
-                }
+            0 if recursive_calls == 0
+                && call_cost.complexity.is_constant()
+                && loop_cost.is_constant() =>
+            {
+                details.push("No loops or recursion detected".to_string());
             }
             1 => {
-                complexity = complexity.max(Complexity::Linear);
+                complexity = complexity.plus(&loop_cost);
                 details.push("Single loop detected".to_string());
 
                 if has_binary_search {
-                    complexity = Complexity::Logarithmic;
+                    complexity = Complexity::logarithmic();
                     details.push("Binary search pattern overrides linear complexity".to_string());
                     confidence = 0.9f64;
                 }
             }
             2 => {
-                complexity = complexity.max(Complexity::Quadratic);
+                complexity = complexity.plus(&loop_cost);
                 details.push("Nested loops detected (depth: 2)".to_string());
                 confidence = 0.85f64;
             }
             3 => {
-                complexity = complexity.max(Complexity::Cubic);
+                complexity = complexity.plus(&loop_cost);
                 details.push("Triple nested loops detected".to_string());
                 confidence = 0.85f64;
             }
             n if n > 3 => {
-                complexity = complexity.max(Complexity::Polynomial);
+                complexity = complexity.plus(&loop_cost);
                 details.push(format!("Deeply nested loops (depth: {})", n));
                 confidence = 0.7f64;
             }
-            _ => {}
+            // Collections built outside any loop
+            _ => complexity = complexity.plus(&loop_cost),
+        }
+
+        if cost_tree.children().len() > 1 {
+            details.push(format!(
+                "{} sequential loop blocks: costs add rather than multiply",
+                cost_tree.children().len()
+            ));
+        }
+        if let Some(dominant) = cost_tree.dominant_child() {
+            details.push(format!(
+                "Dominant block: {} ({})",
+                dominant.describe(),
+                dominant.cost()
+            ));
         }
+        details.extend(cost::amortized_appends(tokens, &func.block));
+
+        // Calls to other functions of the file cost what the callee costs
+        complexity = complexity.plus(&call_cost.complexity);
+        details.extend(call_cost.details);
+        let work = loop_cost.plus(&call_cost.complexity);
 
         // Analyze recursion patterns
+        let mut recursion = None;
+        let mut unbalanced = None;
         if recursive_calls > 0 {
-            if self.is_tail_recursive(&function_body, &func.name) {
-                complexity = complexity.max(Complexity::Linear);
+            if group.len() > 1 {
+                let others: Vec<String> = group
+                    .iter()
+                    .filter(|&&name| name != func.name)
+                    .map(|name| format!("'{}'", name))
+                    .collect();
+                details.push(format!("Mutually recursive with {}", others.join(", ")));
+            }
+            let measured: Vec<usize> = sites
+                .iter()
+                .filter(|site| summaries[site.callee].is_some())
+                .map(|site| site.index)
+                .collect();
+            let recurrence = recurrence::extract(
+                tokens,
+                &func.block,
+                group,
+                &measured,
+                &mut symbols,
+                &work,
+                &language,
+            );
+            let branching = recurrence
+                .as_ref()
+                .map_or(recursive_calls > 1, |(r, _)| r.is_branching());
+
+            if group
+                .iter()
+                .any(|name| self.is_tail_recursive(statements, name))
+            {
                 details.push("Tail recursion detected".to_string());
                 confidence = 0.8f64;
-            } else if self.is_divide_and_conquer(&function_body) {
-                complexity = complexity.max(Complexity::Linearithmic);
+            } else if recurrence
+                .as_ref()
+                .is_some_and(|(r, _)| r.is_divide_and_conquer())
+            {
                 details.push("Divide and conquer recursion detected".to_string());
                 confidence = 0.85f64;
-            } else if recursive_calls > 1 {
-                if self.is_fibonacci_like(&function_body, &func.name) {
-                    complexity = complexity.max(Complexity::Exponential);
+            } else if branching {
+                if self.is_fibonacci_like(statements, &func.name) {
                     details.push("Exponential recursion (fibonacci-like) detected".to_string());
                     confidence = 0.9f64;
                 } else {
-                    complexity = complexity.max(Complexity::Exponential);
                     details.push("Multiple recursive calls detected".to_string());
                     confidence = 0.7f64;
                 }
             } else {
-                complexity = complexity.max(Complexity::Linear);
                 details.push("Simple recursion detected".to_string());
                 confidence = 0.7f64;
             }
+
+            match recurrence {
+                Some((recurrence, notes)) => {
+                    let solution = recurrence.solve();
+                    details.extend(notes);
+                    details.push(format!("Recurrence: {}", recurrence));
+                    details.push(solution.derivation);
+                    if recurrence.assumed() > 0 {
+                        details.push(format!(
+                            "{} recursive call(s) with no visible size reduction assumed to take n-1",
+                            recurrence.assumed()
+                        ));
+                        confidence = confidence.min(0.6f64);
+                    }
+                    if recurrence.is_divide_and_conquer() && cases::uses_pivot(statements) {
+                        let worst = recurrence.unbalanced();
+                        unbalanced = Some((worst.solve().complexity, worst.to_string()));
+                    }
+                    let memo = memo::memo_guard(tokens, &func.block);
+                    recursion = Some(space::Recursion {
+                        depth: recurrence.depth(),
+                        divides: recurrence.is_divide_and_conquer(),
+                        memo: memo.as_ref().map(|_| recurrence.states(&mut symbols)),
+                    });
+                    match memo {
+                        Some(guard) => {
+                            let per_state = work.plus(recurrence.work());
+                            let memo = recurrence.memoized(&per_state, &mut symbols);
+                            details.push(format!("Memoization detected ({guard})"));
+                            details.push(format!("Without memoization: {}", solution.complexity));
+                            details.push(memo.derivation);
+                            complexity = complexity.max(memo.complexity.min(solution.complexity));
+                            confidence = confidence.max(0.8f64);
+                        }
+                        None => complexity = complexity.max(solution.complexity),
+                    }
+                }
+                None => {
+                    complexity = complexity.max(Complexity::linear());
+                    recursion = Some(space::Recursion {
+                        depth: Complexity::variable(&symbols.primary()),
+                        divides: false,
+                        memo: None,
+                    });
+                }
+            }
         }
 
-        // Check for dynamic programming
-        if has_dynamic_programming {
-            details
-                .push("Dynamic programming pattern detected - may reduce complexity".to_string());
-            confidence = confidence.min(0.6f64);
+        // Bottom-up dynamic programming: one transition per table entry
+        for table in &tables {
+            details.push(table.describe());
+            complexity = complexity.max(table.time());
         }
 
         // Check for sorting
         if has_sorting {
-            complexity = complexity.max(Complexity::Linearithmic);
+            complexity = complexity.max(Complexity::linearithmic());
             details.push("Sorting operation detected".to_string());
         }
 
-        // Special case: factorial-like patterns
-        if self.detect_factorial_pattern(&function_body) {
-            complexity = complexity.max(Complexity::Factorial);
-            details.push("Factorial complexity pattern detected".to_string());
-            confidence = 0.8f64;
-        }
-
-        ComplexityResult {
-            complexity: complexity.to_string().to_string(),
+        let mut cases = Cases::new(&complexity);
+        if complexity == loop_cost
+            && let Some((best, reason)) =
+                cases::single_pass(&cost_tree, &cases::early_exits(tokens, &func.block))
+        {
+            cases.lower_best(best, reason);
+        }
+        if let Some((worst, recurrence)) = unbalanced {
+            cases.raise_worst(
+                worst.clone(),
+                format!(
+                    "Worst case {}: a pivot that is always the smallest or largest element \
+                     gives {}",
+                    worst, recurrence
+                ),
+            );
+        }
+        if let Some((factor, reason)) = cases::hash_collisions(tokens, &func.block, &mut symbols) {
+            cases.raise_worst(complexity.times(&factor), reason);
+        }
+
+        let space_complexity = space::space(
+            tokens,
+            &func.block,
+            &mut symbols,
+            &language,
+            recursion.as_ref(),
+            &tables,
+        );
+
+        // Only describe the variables that survive into the result
+        let mut used = complexity.variables();
+        used.extend(space_complexity.complexity().variables());
+        used.extend(cases.variables());
+        let variables: BTreeMap<String, String> = symbols
+            .legend()
+            .into_iter()
+            .filter(|(symbol, _)| used.contains(symbol))
+            .collect();
+        if variables.len() > 1 || variables.iter().any(|(symbol, source)| symbol != source) {
+            let legend: Vec<String> = variables
+                .iter()
+                .map(|(symbol, source)| format!("{} = {}", symbol, source))
+                .collect();
+            details.push(format!("Loop variables: {}", legend.join(", ")));
+        }
+
+        let summary = calls::Summary {
+            complexity: complexity.clone(),
+            parameters: parameters.clone(),
+            sources: variables
+                .keys()
+                .filter_map(|symbol| Some((symbol.clone(), symbols.source(symbol)?.clone())))
+                .collect(),
+            chain: call_cost.chain,
+        };
+        let analysis = ComplexityResult {
+            complexity,
             confidence,
             details,
             line_start: func.start_line,
             line_end: func.end_line,
-        }
+            parameters,
+            variables,
+            cost_tree,
+            space_complexity,
+            cases,
+        };
+        (analysis, summary)
     }
 
-    fn calculate_loop_depth(&self, code: &str) -> usize {
-        let mut max_depth = 0usize;
-        let mut current_depth = 0usize;
-
-        for line in code.lines().take(1000) {
-            // Limit processing
-            let trimmed = line.trim();
-
-            // Skip comments and empty lines
-            if trimmed.is_empty() || trimmed.starts_with("//") || trimmed.starts_with("#") {
-                continue;
-            }
-
-            // Detect loop starts
-            if self.is_loop_start(trimmed) {
-                current_depth += 1;
-                max_depth = max_depth.max(current_depth);
-            }
-
-            // Detect block ends based on language
-            match self.language.to_lowercase().as_str() {
-                "python" => {
-                    if self.is_python_dedent(line) {
-                        current_depth = current_depth.saturating_sub(1);
-                    }
-                }
-                _ => {
-                    let close_braces = line.matches('}').count();
-                    current_depth = current_depth.saturating_sub(close_braces);
-                }
-            }
-        }
-
-        max_depth.min(10) // Cap at reasonable depth
-    }
-
-    fn is_loop_start(&self, line: &str) -> bool {
-        match self.language.to_lowercase().as_str() {
-            "python" => line.starts_with("for ") || line.starts_with("while "),
-            _ => {
-                line.starts_with("for ")
-                    || line.starts_with("for(")
-                    || line.starts_with("while ")
-                    || line.starts_with("while(")
-                    || line.contains("for (")
-                    || line.contains("while (")
-            }
-        }
+    fn calls_builtin(&self, tokens: &[Token], builtin: &str) -> bool {
+        let path: Vec<&str> = builtin.split('.').collect();
+        let span = path.len() * 2 - 1;
+
+        tokens.windows(span + 1).any(|window| {
+            path.iter()
+                .enumerate()
+                .all(|(i, segment)| window[i * 2].is_ident(segment))
+                && window
+                    .iter()
+                    .skip(1)
+                    .step_by(2)
+                    .take(path.len() - 1)
+                    .all(|t| t.is_punct("."))
+                && window[span].is_punct("(")
+        })
     }
 
-    fn is_python_dedent(&self, line: &str) -> bool {
-        !line.trim().is_empty()
-            && !line.starts_with(' ')
-            && !line.starts_with('\t')
-            && !line.trim().starts_with('#')
+    /// Indices of calls to `function_name`.
+    fn find_calls(&self, tokens: &[Token], function_name: &str) -> Vec<usize> {
+        (0..tokens.len())
+            .filter(|&i| tokens[i].text == function_name && lexer::is_call(tokens, i))
+            .collect()
     }
 
-    fn count_function_calls(&self, code: &str, function_name: &str) -> usize {
+    fn count_function_calls(&self, tokens: &[Token], function_name: &str) -> usize {
         if function_name.len() > 50 {
             // Avoid processing very long names
             return 0;
         }
-        let pattern = format!("{}(", function_name);
-        code.matches(&pattern).count().min(100) // Cap at reasonable number
-    }
-
-    fn detect_binary_search(&self, code: &str) -> bool {
-        let has_mid = code.contains("mid") || code.contains("middle");
-        let has_bounds = (code.contains("left") || code.contains("low") || code.contains("start"))
-            && (code.contains("right") || code.contains("high") || code.contains("end"));
-        let has_division = code.contains("/2")
-            || code.contains(">> 1")
-            || code.contains("div 2")
-            || code.contains("// 2");
-
-        has_mid && has_bounds && has_division
+        self.find_calls(tokens, function_name).len().min(100) // Cap at reasonable number
     }
 
-    fn detect_sorting(&self, code: &str) -> bool {
-        let sorting_patterns = [
-            "sort(",
-            ".sort(",
-            "sorted(",
-            "quicksort",
-            "mergesort",
-            "Arrays.sort",
-            "Collections.sort",
-        ];
-
-        sorting_patterns
-            .iter()
-            .any(|pattern| code.to_lowercase().contains(&pattern.to_lowercase()))
+    fn has_division_by_two(&self, tokens: &[Token]) -> bool {
+        tokens.windows(2).any(|pair| {
+            let divides =
+                pair[0].is_punct("/") || pair[0].is_punct("//") || pair[0].is_ident("div");
+            (divides && pair[1].text == "2") || (pair[0].is_punct(">>") && pair[1].text == "1")
+        })
     }
 
-    fn detect_dynamic_programming(&self, code: &str) -> bool {
-        let dp_indicators = ["memo", "cache", "dp[", "table[", "@lru_cache", "@cache"];
-
-        dp_indicators
-            .iter()
-            .any(|indicator| code.to_lowercase().contains(&indicator.to_lowercase()))
+    fn has_identifier_containing(&self, tokens: &[Token], fragments: &[&str]) -> bool {
+        tokens.iter().any(|t| {
+            t.kind == TokenKind::Identifier && {
+                let name = t.text.to_lowercase();
+                fragments.iter().any(|fragment| name.contains(fragment))
+            }
+        })
     }
 
-    fn detect_factorial_pattern(&self, code: &str) -> bool {
-        code.contains("factorial") || (code.contains("*") && code.contains("n-1"))
-    }
+    fn detect_binary_search(&self, tokens: &[Token]) -> bool {
+        let has_mid = self.has_identifier_containing(tokens, &["mid"]);
+        let has_bounds = self.has_identifier_containing(tokens, &["left", "low", "start"])
+            && self.has_identifier_containing(tokens, &["right", "high", "end"]);
+        let has_division = self.has_division_by_two(tokens);
 
-    fn is_divide_and_conquer(&self, code: &str) -> bool {
-        let has_division = code.contains("mid") || code.contains("/2") || code.contains(">> 1");
-        let has_merge_combine = code.contains("merge") || code.contains("combine");
-        has_division && has_merge_combine
+        has_mid && has_bounds && has_division
     }
 
-    fn is_tail_recursive(&self, code: &str, function_name: &str) -> bool {
-        let lines: Vec<&str> = code.lines().collect();
-        let pattern = format!("{}(", function_name);
+    fn detect_sorting(&self, tokens: &[Token]) -> bool {
+        let calls_sort = (0..tokens.len()).any(|i| {
+            lexer::is_call(tokens, i) && matches!(tokens[i].text.as_str(), "sort" | "sorted")
+        });
 
-        for line in lines.iter().rev().take(10) {
-            // Only check last few lines
-            let trimmed = line.trim();
-            if trimmed.contains(&pattern) {
-                return trimmed.starts_with("return ");
-            }
-        }
-        false
+        calls_sort || self.has_identifier_containing(tokens, &["quicksort", "mergesort"])
     }
 
-    fn is_fibonacci_like(&self, code: &str, function_name: &str) -> bool {
-        let pattern = format!("{}(", function_name);
-        let call_count = code.matches(&pattern).count();
-
-        call_count >= 2
-            && (code.contains("n-1") || code.contains("n - 1"))
-            && (code.contains("n-2") || code.contains("n - 2"))
+    /// Returns true when `name - amount` appears for some identifier `name`.
+    fn has_decrement(&self, tokens: &[Token], amount: &str) -> bool {
+        tokens.windows(3).any(|w| {
+            w[0].kind == TokenKind::Identifier && w[1].is_punct("-") && w[2].text == amount
+        })
     }
 
-    fn get_overall_complexity(&self, functions: &[FunctionAnalysis]) -> String {
-        if functions.is_empty() {
-            return "O(1)".to_string();
-        }
+    fn is_tail_recursive(&self, tokens: &[Token], function_name: &str) -> bool {
+        let Some(&last_call) = self.find_calls(tokens, function_name).last() else {
+            return false;
+        };
 
-        let complexity_order = [
-            "O(n!)",
-            "O(2ⁿ)",
-            "O(n^k)",
-            "O(n³)",
-            "O(n²)",
-            "O(n log n)",
-            "O(n)",
-            "O(log n)",
-            "O(1)",
-        ];
-
-        for &complexity in &complexity_order {
-            if functions.iter().any(|f| f.complexity == complexity) {
-                return complexity.to_string();
-            }
-        }
+        let returned = last_call > 0 && tokens[last_call - 1].is_keyword("return");
+        let statement_ends = match lexer::matching_close(tokens, last_call + 1) {
+            Some(close) => tokens.get(close + 1).is_none_or(|next| {
+                next.is_punct(";") || next.is_punct("}") || next.line != tokens[close].line
+            }),
+            None => false,
+        };
 
-        "O(1)".to_string()
+        returned && statement_ends
     }
-}
 
-#[wasm_bindgen]
-pub fn analyze_complexity(code: &str, language: &str) -> Result<JsValue, JsValue> {
-    // Early validation
-    if code.is_empty() {
-        return Err(JsValue::from_str("Empty code provided"));
-    }
+    fn is_fibonacci_like(&self, tokens: &[Token], function_name: &str) -> bool {
+        let call_count = self.find_calls(tokens, function_name).len();
 
-    if code.len() > 500_000 {
-        // 500KB limit
-        return Err(JsValue::from_str("Code too large to analyze"));
+        call_count >= 2 && self.has_decrement(tokens, "1") && self.has_decrement(tokens, "2")
     }
 
-    console_log!(
-        "Analyzing complexity for {} code ({} chars)",
-        language,
-        code.len()
-    );
-
-    let analyzer = ComplexityAnalyzer::new(language);
-    let result = analyzer.analyze(code);
-
-    serde_wasm_bindgen::to_value(&result).map_err(|e| {
-        console_log!("Serialization error: {}", e);
-        JsValue::from_str("Failed to serialize result")
-    })
-}
-
-#[wasm_bindgen]
-pub fn get_supported_languages() -> Vec<JsValue> {
-    vec![
-        JsValue::from_str("javascript"),
-        JsValue::from_str("typescript"),
-        JsValue::from_str("python"),
-        JsValue::from_str("java"),
-        JsValue::from_str("c"),
-        JsValue::from_str("cpp"),
-        JsValue::from_str("rust"),
-    ]
-}
-
-#[wasm_bindgen]
-pub fn init() {
-    console_log!("Rust-WASM Big O Analyzer initialized");
-}
-
-#[wasm_bindgen(start)]
-pub fn main() {
-    #[cfg(feature = "console_error_panic_hook")]
-    console_error_panic_hook::set_once();
-
-    console_log!("WASM module loaded successfully");
+    fn get_overall_complexity(&self, functions: &[FunctionAnalysis]) -> Complexity {
+        functions
+            .iter()
+            .map(|f| f.complexity.clone())
+            .max()
+            .unwrap_or_else(Complexity::constant)
+    }
 }
@@ -0,0 +1,1318 @@
+//! Loop header analysis.
+//!
+//! Works out what a loop iterates over (`for row in rows`, `i < cols.length`,
+//! `range(n)`) so that loops over different inputs get different
+//! complexity variables instead of all sharing `n`, and how fast it gets
+//! there (`range(10)` is constant, `i *= 2` is logarithmic).
+
+use crate::annotations::Assumption;
+use crate::blocks::{Block, BlockKind};
+use crate::lexer::{Token, TokenKind};
+use std::collections::{BTreeMap, HashMap};
+
+/// Symbols handed out to loop sources, in order.
+const SYMBOLS: &[&str] = &["n", "m", "k", "p", "q", "r", "s", "t"];
+
+/// What determines the number of iterations of a loop.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SizeSource {
+    /// Elements or indices of a collection: `for x in rows`, `i < rows.length`.
+    Length(String),
+    /// A numeric bound: `range(n)`, `i < count`.
+    Value(String),
+}
+
+impl SizeSource {
+    pub fn name(&self) -> &str {
+        match self {
+            SizeSource::Length(name) | SizeSource::Value(name) => name,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            SizeSource::Length(name) => format!("len({})", name),
+            SizeSource::Value(name) => name.clone(),
+        }
+    }
+}
+
+/// Methods whose result has as many elements as the receiver.
+const ELEMENT_METHODS: &[&str] = &[
+    "items",
+    "keys",
+    "values",
+    "iter",
+    "iter_mut",
+    "into_iter",
+    "chars",
+    "bytes",
+    "entrySet",
+    "keySet",
+    "enumerate",
+    "entries",
+    "forEach",
+];
+
+/// Calls that wrap a collection without changing its length.
+const ELEMENT_WRAPPERS: &[&str] = &[
+    "enumerate",
+    "zip",
+    "reversed",
+    "sorted",
+    "list",
+    "set",
+    "tuple",
+    "iter",
+    "Object",
+];
+
+/// Size accessors: `len(x)`, `x.length`, `x.size()`, `x.len()`, `strlen(x)`.
+const LENGTH_FUNCTIONS: &[&str] = &["len", "strlen", "size"];
+const LENGTH_PROPERTIES: &[&str] = &["length", "size", "len", "count", "Count"];
+
+/// Identifiers that are literals rather than sizes.
+const LITERAL_NAMES: &[&str] = &[
+    "null",
+    "None",
+    "nil",
+    "NULL",
+    "nullptr",
+    "undefined",
+    "true",
+    "false",
+    "True",
+    "False",
+];
+
+/// Source text of a short expression such as `grid[0]`.
+fn expression_text(tokens: &[Token]) -> String {
+    tokens.iter().map(|t| t.text.as_str()).collect()
+}
+
+/// Splits `tokens` on `separator` at bracket depth 0.
+pub fn split_top_level<'a>(tokens: &'a [Token], separator: &str) -> Vec<&'a [Token]> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        if token.kind == TokenKind::Punctuation {
+            match token.text.as_str() {
+                "(" | "[" | "{" => depth += 1,
+                ")" | "]" | "}" => depth = depth.saturating_sub(1),
+                text if text == separator && depth == 0 => {
+                    parts.push(&tokens[start..i]);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+    }
+    parts.push(&tokens[start..]);
+    parts
+}
+
+/// Removes one pair of parentheses wrapping the whole of `tokens`.
+fn strip_parens(tokens: &[Token]) -> &[Token] {
+    match (tokens.first(), tokens.last()) {
+        (Some(first), Some(last)) if first.is_punct("(") && last.is_punct(")") => {
+            let mut depth = 0usize;
+            for (i, token) in tokens.iter().enumerate() {
+                if token.is_punct("(") {
+                    depth += 1;
+                } else if token.is_punct(")") {
+                    depth -= 1;
+                    if depth == 0 && i + 1 < tokens.len() {
+                        return tokens;
+                    }
+                }
+            }
+            &tokens[1..tokens.len() - 1]
+        }
+        _ => tokens,
+    }
+}
+
+/// Drops a leading `self.` / `this.` so `self.items` reads as `items`.
+fn strip_receiver(tokens: &[Token]) -> &[Token] {
+    match tokens {
+        [first, dot, rest @ ..]
+            if (first.text == "self" || first.text == "this")
+                && dot.is_punct(".")
+                && !rest.is_empty() =>
+        {
+            rest
+        }
+        _ => tokens,
+    }
+}
+
+/// Arguments of the call whose `(` is at `open`.
+pub fn call_arguments(tokens: &[Token], open: usize) -> Vec<&[Token]> {
+    let close = crate::lexer::matching_close(tokens, open).unwrap_or(tokens.len());
+    let inner = &tokens[open + 1..close.min(tokens.len())];
+    if inner.is_empty() {
+        return Vec::new();
+    }
+    split_top_level(inner, ",")
+}
+
+/// Reads an expression that evaluates to a number of iterations:
+/// `len(rows)`, `rows.length`, `n`, `n + 1`.
+pub fn value_source(tokens: &[Token]) -> Option<SizeSource> {
+    let tokens = strip_receiver(strip_parens(tokens));
+
+    for (i, token) in tokens.iter().enumerate() {
+        if token.kind != TokenKind::Identifier {
+            continue;
+        }
+
+        // `len(x)`, `strlen(x)`
+        if LENGTH_FUNCTIONS.contains(&token.text.as_str())
+            && tokens.get(i + 1).is_some_and(|t| t.is_punct("("))
+        {
+            let arguments = call_arguments(tokens, i + 1);
+            return arguments
+                .first()
+                .map(|arg| strip_receiver(arg))
+                .filter(|arg| arg.first().is_some_and(|t| t.kind == TokenKind::Identifier))
+                .map(|arg| SizeSource::Length(expression_text(arg)));
+        }
+
+        // `x.length`, `x.size()`, `x.len()`
+        if tokens.get(i + 1).is_some_and(|t| t.is_punct("."))
+            && tokens
+                .get(i + 2)
+                .is_some_and(|t| LENGTH_PROPERTIES.contains(&t.text.as_str()))
+        {
+            return Some(SizeSource::Length(token.text.clone()));
+        }
+
+        // `grid[0].length`, `grid[0].size()`
+        if tokens.get(i + 1).is_some_and(|t| t.is_punct("[")) {
+            let close = crate::lexer::matching_close(tokens, i + 1)?;
+            if tokens.get(close + 1).is_some_and(|t| t.is_punct("."))
+                && tokens
+                    .get(close + 2)
+                    .is_some_and(|t| LENGTH_PROPERTIES.contains(&t.text.as_str()))
+            {
+                return Some(SizeSource::Length(expression_text(&tokens[i..=close])));
+            }
+            return None;
+        }
+
+        // `seen.get(key)`, `compute(n)`: the size of a call result is unknown
+        if LITERAL_NAMES.contains(&token.text.as_str())
+            || tokens.get(i + 1).is_some_and(|t| t.is_punct("("))
+        {
+            return None;
+        }
+        if !tokens.get(i + 1).is_some_and(|t| t.is_punct(".")) {
+            return Some(SizeSource::Value(token.text.clone()));
+        }
+    }
+
+    None
+}
+
+/// Reads the iterable of a `for ... in` loop.
+pub fn iterable_source(tokens: &[Token]) -> Option<SizeSource> {
+    let mut tokens = strip_parens(tokens);
+    while let Some(first) = tokens.first() {
+        if first.is_punct("&") || first.is_punct("*") || first.is_keyword("mut") {
+            tokens = &tokens[1..];
+        } else {
+            break;
+        }
+    }
+    let tokens = strip_receiver(tokens);
+
+    // Rust ranges: `0..n`, `(0..=n).rev()`
+    let range_body = match tokens.first() {
+        Some(first) if first.is_punct("(") => {
+            let close = crate::lexer::matching_close(tokens, 0).unwrap_or(tokens.len());
+            &tokens[1..close.min(tokens.len())]
+        }
+        _ => tokens,
+    };
+    if let Some(dots) = range_body
+        .iter()
+        .position(|t| t.is_punct("..") || t.is_punct("..="))
+    {
+        return value_source(&range_body[dots + 1..]);
+    }
+
+    let first = tokens.first()?;
+    if first.kind != TokenKind::Identifier {
+        return None;
+    }
+
+    if tokens.get(1).is_some_and(|t| t.is_punct("(")) {
+        let arguments = call_arguments(tokens, 1);
+        if first.text == "range" || first.text == "xrange" {
+            // `range(stop)` or `range(start, stop[, step])`
+            let stop = if arguments.len() == 1 {
+                arguments.first()
+            } else {
+                arguments.get(1)
+            };
+            return stop.and_then(|arg| value_source(arg));
+        }
+        if ELEMENT_WRAPPERS.contains(&first.text.as_str()) {
+            return arguments.first().and_then(|arg| iterable_source(arg));
+        }
+        return None;
+    }
+
+    // `Object.entries(map)`, `Arrays.asList(xs)`
+    if matches!(first.text.as_str(), "Object" | "Arrays" | "Array")
+        && tokens.get(3).is_some_and(|t| t.is_punct("("))
+    {
+        return call_arguments(tokens, 3)
+            .first()
+            .and_then(|arg| iterable_source(arg));
+    }
+
+    // `rows`, `rows.items()`, `grid[i]`
+    if tokens.get(1).is_some_and(|t| t.is_punct("."))
+        && !tokens
+            .get(2)
+            .is_some_and(|t| ELEMENT_METHODS.contains(&t.text.as_str()))
+    {
+        return None;
+    }
+    Some(SizeSource::Length(first.text.clone()))
+}
+
+/// Reads a loop condition such as `i < n`, `j < rows.length`, `queue`.
+pub fn condition_source(tokens: &[Token]) -> Option<SizeSource> {
+    let tokens = strip_parens(tokens);
+    let first_clause = split_top_level(tokens, "&&")[0];
+    let first_clause = first_clause
+        .iter()
+        .position(|t| t.is_keyword("and") || t.is_keyword("or"))
+        .map_or(first_clause, |end| &first_clause[..end]);
+
+    let comparison = first_clause.iter().position(|t| {
+        t.kind == TokenKind::Punctuation
+            && matches!(t.text.as_str(), "<" | "<=" | ">" | ">=" | "!=")
+    });
+
+    if let Some(op) = comparison {
+        let (left, right) = (&first_clause[..op], &first_clause[op + 1..]);
+        let right_is_literal = right
+            .iter()
+            .all(|t| t.kind != TokenKind::Identifier || LITERAL_NAMES.contains(&t.text.as_str()));
+        return if right_is_literal {
+            // `while n > 0`
+            value_source(left)
+        } else {
+            value_source(right)
+        };
+    }
+
+    // `while queue`, `while (!stack.isEmpty())`, `while let Some(x) = stack.pop()`
+    first_clause
+        .iter()
+        .enumerate()
+        .rev()
+        .find(|(i, t)| {
+            t.kind == TokenKind::Identifier
+                && !LITERAL_NAMES.contains(&t.text.as_str())
+                && first_clause
+                    .get(i + 1)
+                    .is_none_or(|next| next.is_punct("."))
+        })
+        .map(|(_, t)| SizeSource::Length(t.text.clone()))
+}
+
+/// How the number of iterations of a loop grows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoopBound {
+    /// Runs a fixed number of times: `range(10)`, `i < 100`, with the
+    /// count when it can be read off the header.
+    Constant(Option<u64>),
+    /// Steps through its source by a constant amount: `i++`, `for x in xs`.
+    Linear(Option<SizeSource>),
+    /// Multiplies or divides its index: `i *= 2`, `n //= 2`.
+    Logarithmic(Option<SizeSource>),
+}
+
+impl LoopBound {
+    pub fn source(&self) -> Option<&SizeSource> {
+        match self {
+            LoopBound::Constant(_) => None,
+            LoopBound::Linear(source) | LoopBound::Logarithmic(source) => source.as_ref(),
+        }
+    }
+}
+
+/// How a loop index changes each iteration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    /// `i++`, `i += 2`, `i = i - 1`
+    Additive,
+    /// `i *= 2`, `n //= 2`, `i <<= 1`, `hi = mid - 1`
+    Multiplicative,
+}
+
+/// Whether `tokens` is a numeric expression such as `10` or `2 * 50`.
+fn is_literal(tokens: &[Token]) -> bool {
+    let tokens = strip_parens(tokens);
+    tokens.iter().any(|t| t.kind == TokenKind::Number)
+        && tokens
+            .iter()
+            .all(|t| t.kind == TokenKind::Number || t.kind == TokenKind::Punctuation)
+}
+
+/// Whether `tokens` is a literal of a fixed size: `5`, `"abc"` or
+/// `[1, 2, 3]`, but not an empty `[]` that is filled later.
+fn is_constant_value(tokens: &[Token]) -> bool {
+    let tokens = strip_parens(tokens);
+    is_literal(tokens)
+        || is_literal_collection(tokens) && tokens.len() > 2
+        || matches!(tokens, [token] if matches!(token.kind, TokenKind::String | TokenKind::Char))
+}
+
+/// Whether `tokens` is a collection literal such as `[(0, 1), (1, 0)]`.
+fn is_literal_collection(tokens: &[Token]) -> bool {
+    tokens
+        .first()
+        .is_some_and(|t| t.is_punct("[") || t.is_punct("(") || t.is_punct("{"))
+        && tokens.iter().all(|t| {
+            matches!(
+                t.kind,
+                TokenKind::Number | TokenKind::String | TokenKind::Char | TokenKind::Punctuation
+            )
+        })
+}
+
+/// Whether a loop condition compares an index against a literal: `i < 10`.
+fn is_constant_condition(tokens: &[Token]) -> bool {
+    let tokens = strip_parens(tokens);
+    let Some(op) = tokens.iter().position(|t| {
+        t.kind == TokenKind::Punctuation && matches!(t.text.as_str(), "<" | "<=" | ">" | ">=")
+    }) else {
+        return false;
+    };
+    let (left, right) = (&tokens[..op], &tokens[op + 1..]);
+    match tokens[op].text.as_str() {
+        "<" | "<=" => is_literal(right) && !right.iter().all(|t| t.text == "0"),
+        _ => is_literal(left),
+    }
+}
+
+/// Operators that change `variable` geometrically when applied to it.
+const MULTIPLICATIVE_ASSIGNMENTS: &[&str] = &["*=", "/=", "//=", "<<=", ">>=", ">>>="];
+const MULTIPLICATIVE_OPERATORS: &[&str] = &["*", "/", "//", "<<", ">>", ">>>"];
+const ADDITIVE_ASSIGNMENTS: &[&str] = &["+=", "-=", "++", "--"];
+const GROWING_ASSIGNMENTS: &[&str] = &["+=", "*=", "**=", "<<=", "|="];
+const GROWING_OPERATORS: &[&str] = &["+", "*", "**", "<<", "|"];
+
+/// Names assigned a halving expression, such as `mid = (lo + hi) // 2`.
+pub fn midpoints(tokens: &[Token]) -> Vec<&str> {
+    tokens
+        .iter()
+        .enumerate()
+        .filter(|(i, t)| {
+            t.kind == TokenKind::Identifier && tokens.get(i + 1).is_some_and(|t| t.is_punct("="))
+        })
+        .filter(|(i, t)| {
+            tokens[i + 2..]
+                .iter()
+                .take_while(|next| next.line == t.line && !next.is_punct(";"))
+                .any(|next| {
+                    matches!(next.text.as_str(), "/" | "//" | ">>" | ">>>")
+                        && next.kind == TokenKind::Punctuation
+                })
+        })
+        .map(|(_, t)| t.text.as_str())
+        .collect()
+}
+
+/// How `variable` is updated within `tokens`, preferring multiplicative
+/// updates when both kinds appear.
+fn step_of(tokens: &[Token], variable: &str) -> Option<Step> {
+    let midpoints = midpoints(tokens);
+    let mut step = None;
+    for (i, token) in tokens.iter().enumerate() {
+        if !token.is_ident(variable) {
+            continue;
+        }
+        let next = tokens.get(i + 1);
+        let previous = i.checked_sub(1).map(|p| &tokens[p]);
+        let found = match next {
+            Some(op) if MULTIPLICATIVE_ASSIGNMENTS.contains(&op.text.as_str()) => {
+                Some(Step::Multiplicative)
+            }
+            Some(op) if ADDITIVE_ASSIGNMENTS.contains(&op.text.as_str()) => Some(Step::Additive),
+            Some(op) if op.is_punct("=") => {
+                let rhs: Vec<&Token> = tokens[i + 2..]
+                    .iter()
+                    .take_while(|t| t.line == token.line && !t.is_punct(";"))
+                    .collect();
+                let uses_self = rhs.iter().any(|t| t.is_ident(variable));
+                let scales = rhs.iter().any(|t| {
+                    t.kind == TokenKind::Punctuation
+                        && MULTIPLICATIVE_OPERATORS.contains(&t.text.as_str())
+                });
+                let from_midpoint = rhs
+                    .first()
+                    .is_some_and(|t| midpoints.contains(&t.text.as_str()));
+                if (uses_self && scales) || from_midpoint {
+                    Some(Step::Multiplicative)
+                } else if uses_self {
+                    Some(Step::Additive)
+                } else {
+                    None
+                }
+            }
+            _ => match previous {
+                Some(op) if op.is_punct("++") || op.is_punct("--") => Some(Step::Additive),
+                _ => None,
+            },
+        };
+        match found {
+            Some(Step::Multiplicative) => return Some(Step::Multiplicative),
+            Some(Step::Additive) => step = Some(Step::Additive),
+            None => {}
+        }
+    }
+    step
+}
+
+/// Identifiers compared in a loop condition: `lo` and `hi` in `lo <= hi`.
+fn condition_variables(tokens: &[Token]) -> Vec<&str> {
+    tokens
+        .iter()
+        .enumerate()
+        .filter(|(i, t)| {
+            t.kind == TokenKind::Identifier
+                && !LITERAL_NAMES.contains(&t.text.as_str())
+                && !tokens
+                    .get(i + 1)
+                    .is_some_and(|next| next.is_punct("(") || next.is_punct("."))
+                && !i.checked_sub(1).is_some_and(|p| tokens[p].is_punct("."))
+        })
+        .map(|(_, t)| t.text.as_str())
+        .collect()
+}
+
+/// Tokens of a loop's own body: nested loops and functions update their
+/// own indices.
+fn body_tokens(tokens: &[Token], block: &Block) -> Vec<Token> {
+    let nested: Vec<_> = block
+        .children
+        .iter()
+        .filter(|child| child.kind == BlockKind::Loop || child.is_function())
+        .map(|child| child.span.clone())
+        .collect();
+    (block.header.end..block.span.end)
+        .filter(|i| !nested.iter().any(|span| span.contains(i)))
+        .map(|i| tokens[i].clone())
+        .collect()
+}
+
+/// Header of a loop without its keyword or wrapping parentheses.
+fn loop_header<'a>(tokens: &'a [Token], block: &Block) -> &'a [Token] {
+    let mut header = &tokens[block.header.clone()];
+    while header.first().is_some_and(|first| {
+        matches!(
+            first.text.as_str(),
+            "for" | "while" | "async" | "await" | "do"
+        ) && first.kind == TokenKind::Keyword
+    }) {
+        header = &header[1..];
+    }
+    strip_parens(header)
+}
+
+/// Iterations of a loop over `iterable`: constant for `[1, 2, 3]` or
+/// `range(10)`, otherwise one per element.
+pub fn iterable_bound(iterable: &[Token]) -> LoopBound {
+    let iterable = strip_parens(iterable);
+    if let Some((start, stop)) = range_ends(iterable)
+        && let Some(bound) = window(start, stop)
+    {
+        return bound;
+    }
+    if is_literal_collection(iterable) {
+        let elements = strip_parens(&iterable[1..iterable.len().saturating_sub(1)]);
+        let count = split_top_level(elements, ",")
+            .iter()
+            .filter(|element| !element.is_empty())
+            .count();
+        return LoopBound::Constant(Some(count as u64));
+    }
+    if is_constant_range(iterable) {
+        return LoopBound::Constant(range_count(iterable));
+    }
+    LoopBound::Linear(iterable_source(iterable))
+}
+
+/// Classifies the loop `block` from its header and, for `while` loops,
+/// the updates in its body.
+pub fn loop_bound(tokens: &[Token], block: &Block) -> LoopBound {
+    let keyword = tokens[block.span.clone()]
+        .iter()
+        .find(|t| t.kind == TokenKind::Keyword)
+        .map(|t| t.text.as_str());
+    let header = loop_header(tokens, block);
+
+    // C-style `for (init; condition; update)`
+    let clauses = split_top_level(header, ";");
+    if clauses.len() == 3 {
+        let (init, condition, update) = (clauses[0], clauses[1], clauses[2]);
+        let eq = init.iter().position(|t| t.is_punct("="));
+        let index = eq
+            .and_then(|eq| eq.checked_sub(1))
+            .map(|i| init[i].text.as_str());
+        let start = eq.map_or(&init[..0], |eq| &init[eq + 1..]);
+
+        // `for (j = i; j < i + 3; j++)` runs 3 times wherever it starts
+        let stop = strip_parens(condition)
+            .iter()
+            .position(|t| t.is_punct("<") || t.is_punct("<="))
+            .map(|op| &strip_parens(condition)[op + 1..]);
+        if let Some(bound) = stop.and_then(|stop| window(start, stop)) {
+            return bound;
+        }
+
+        let mut source = condition_source(condition);
+        // `for (i = n - 1; i >= 0; i--)` counts down from the initial value
+        let counts_down =
+            matches!(&source, Some(SizeSource::Value(name)) if Some(name.as_str()) == index);
+        if counts_down {
+            if is_literal(start) {
+                let count = literal_value(start).map(|start| start + 1);
+                return LoopBound::Constant(count);
+            }
+            source = value_source(start);
+        } else if is_constant_condition(condition) {
+            return LoopBound::Constant(constant_count(start, condition));
+        }
+
+        let step = index.and_then(|index| {
+            step_of(update, index).or_else(|| step_of(&body_tokens(tokens, block), index))
+        });
+        return match step {
+            Some(Step::Multiplicative) => LoopBound::Logarithmic(source),
+            _ => LoopBound::Linear(source),
+        };
+    }
+
+    // `for x in xs`, `for (const x of xs)`, `for (int x : xs)`
+    let iterable = header.iter().position(|t| {
+        t.is_keyword("in") || t.is_keyword("of") || t.is_ident("of") || t.is_punct(":")
+    });
+    if let (Some(position), Some("for")) = (iterable, keyword) {
+        return iterable_bound(&header[position + 1..]);
+    }
+
+    // `while cond`, `do { } while (cond)`, `loop { }`
+    if keyword == Some("loop") {
+        return LoopBound::Linear(None);
+    }
+    let first_clause = split_top_level(header, "&&")[0];
+    if is_constant_condition(first_clause) {
+        return LoopBound::Constant(None);
+    }
+    let source = condition_source(header);
+    let body = body_tokens(tokens, block);
+    let multiplicative = condition_variables(first_clause)
+        .into_iter()
+        .any(|variable| step_of(&body, variable) == Some(Step::Multiplicative));
+    if multiplicative {
+        LoopBound::Logarithmic(source)
+    } else {
+        LoopBound::Linear(source)
+    }
+}
+
+/// Value of a single integer literal.
+fn literal_value(tokens: &[Token]) -> Option<u64> {
+    match strip_parens(tokens) {
+        [token] if token.kind == TokenKind::Number => token.text.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Iterations of `for (i = start; i < 100; ...)`.
+fn constant_count(start: &[Token], condition: &[Token]) -> Option<u64> {
+    let condition = strip_parens(condition);
+    let op = condition
+        .iter()
+        .position(|t| t.is_punct("<") || t.is_punct("<="))?;
+    let stop = literal_value(&condition[op + 1..])?;
+    let start = literal_value(start).unwrap_or(0);
+    let inclusive = u64::from(condition[op].text == "<=");
+    (stop + inclusive).checked_sub(start)
+}
+
+/// Iterations of `range(4)`, `range(1, 5)` or `0..4`.
+fn range_count(tokens: &[Token]) -> Option<u64> {
+    if let [first, open, ..] = tokens
+        && (first.is_ident("range") || first.is_ident("xrange"))
+        && open.is_punct("(")
+    {
+        let arguments = call_arguments(tokens, 1);
+        return match arguments.as_slice() {
+            [stop] => literal_value(stop),
+            [start, stop, ..] => literal_value(stop)?.checked_sub(literal_value(start)?),
+            _ => None,
+        };
+    }
+    let range = match tokens.first() {
+        Some(first) if first.is_punct("(") => {
+            let close = crate::lexer::matching_close(tokens, 0)?;
+            &tokens[1..close]
+        }
+        _ => tokens,
+    };
+    let dots = range
+        .iter()
+        .position(|t| t.is_punct("..") || t.is_punct("..="))?;
+    let inclusive = u64::from(range[dots].text == "..=");
+    let start = literal_value(&range[..dots]).unwrap_or(0);
+    (literal_value(&range[dots + 1..])? + inclusive).checked_sub(start)
+}
+
+/// Start and stop of `range(start, stop)` or `start..stop`.
+fn range_ends(tokens: &[Token]) -> Option<(&[Token], &[Token])> {
+    if let [first, open, ..] = tokens
+        && (first.is_ident("range") || first.is_ident("xrange"))
+        && open.is_punct("(")
+    {
+        return match call_arguments(tokens, 1)[..] {
+            [start, stop, ..] => Some((start, stop)),
+            _ => None,
+        };
+    }
+    let range = match tokens.first() {
+        Some(first) if first.is_punct("(") => &tokens[1..crate::lexer::matching_close(tokens, 0)?],
+        _ => tokens,
+    };
+    let dots = range
+        .iter()
+        .position(|t| t.is_punct("..") || t.is_punct("..="))?;
+    Some((&range[..dots], &range[dots + 1..]))
+}
+
+/// Iterations of a loop from `start` to `stop` when `stop` is `start` plus
+/// a width, as in `range(i, i + 3)`: the width, whatever `i` is.
+fn window(start: &[Token], stop: &[Token]) -> Option<LoopBound> {
+    let (start, stop) = (strip_parens(start), strip_parens(stop));
+    if start.is_empty()
+        || stop.len() < start.len() + 2
+        || !start.iter().zip(stop).all(|(a, b)| a.text == b.text)
+        || !stop[start.len()].is_punct("+")
+    {
+        return None;
+    }
+    let width = &stop[start.len() + 1..];
+    Some(if is_literal(width) {
+        LoopBound::Constant(literal_value(width))
+    } else {
+        LoopBound::Linear(value_source(width))
+    })
+}
+
+/// Whether an iterable is `range(10)`, `range(1, 10)` or `0..10`: both
+/// ends are literals, so `range(n, 0, -1)` is not constant.
+fn is_constant_range(tokens: &[Token]) -> bool {
+    let (start, stop) = match tokens {
+        [first, open, ..]
+            if (first.is_ident("range") || first.is_ident("xrange")) && open.is_punct("(") =>
+        {
+            let arguments = call_arguments(tokens, 1);
+            match arguments.as_slice() {
+                [stop] => (None, *stop),
+                [start, stop, ..] => (Some(*start), *stop),
+                _ => return false,
+            }
+        }
+        _ => match tokens
+            .iter()
+            .position(|t| t.is_punct("..") || t.is_punct("..="))
+        {
+            Some(dots) => {
+                let begin = usize::from(tokens.first().is_some_and(|t| t.is_punct("(")));
+                let end = tokens[dots + 1..]
+                    .iter()
+                    .position(|t| t.is_punct(")"))
+                    .map_or(tokens.len(), |end| dots + 1 + end);
+                // `..10` starts at zero
+                let start = &tokens[begin.min(dots)..dots];
+                ((!start.is_empty()).then_some(start), &tokens[dots + 1..end])
+            }
+            None => return false,
+        },
+    };
+    start.is_none_or(is_literal) && is_literal(stop)
+}
+
+/// Assigns complexity variables to loop sources within one function.
+#[derive(Debug, Default)]
+pub struct Symbols {
+    assigned: Vec<(SizeSource, String)>,
+    /// Local aliases such as `n = len(arr)`.
+    aliases: HashMap<String, SizeSource>,
+    /// Variables a comment declares bounded by a constant.
+    assumptions: Vec<Assumption>,
+    /// Locals that only ever hold constants, like `k` after `k = 8`.
+    constants: Vec<String>,
+}
+
+impl Symbols {
+    /// Records `name = len(x)`-style assignments found in `tokens`.
+    pub fn with_aliases(tokens: &[Token]) -> Self {
+        let mut aliases = HashMap::new();
+        for (i, token) in tokens.iter().enumerate() {
+            if token.kind != TokenKind::Identifier
+                || !tokens.get(i + 1).is_some_and(|t| t.is_punct("="))
+            {
+                continue;
+            }
+            let rhs: Vec<Token> = tokens[i + 2..]
+                .iter()
+                .take_while(|t| !t.is_punct(";") && t.line == token.line)
+                .cloned()
+                .collect();
+
+            // `n, m = len(a), len(b)` pairs targets and values by position
+            let mut targets = vec![token.text.clone()];
+            let mut j = i;
+            while j >= 2
+                && tokens[j - 1].is_punct(",")
+                && tokens[j - 2].kind == TokenKind::Identifier
+                && tokens[j - 2].line == token.line
+            {
+                targets.insert(0, tokens[j - 2].text.clone());
+                j -= 2;
+            }
+            let values = split_top_level(&rhs, ",");
+            if targets.len() > 1 && targets.len() != values.len() {
+                continue;
+            }
+            let values = if targets.len() > 1 {
+                values
+            } else {
+                vec![&rhs[..]]
+            };
+            for (target, value) in targets.into_iter().zip(values) {
+                if let Some(source @ SizeSource::Length(_)) = value_source(value) {
+                    aliases.insert(target, source);
+                }
+            }
+        }
+        Self {
+            assigned: Vec::new(),
+            aliases,
+            assumptions: Vec::new(),
+            constants: Vec::new(),
+        }
+    }
+
+    /// Whether the expression `tokens` has a fixed size: a literal, or a
+    /// local that only ever holds constants.
+    pub fn is_constant(&self, tokens: &[Token]) -> bool {
+        is_constant_value(tokens)
+            || matches!(strip_parens(tokens), [token] if self.constants.contains(&token.text))
+    }
+
+    /// `bound` read as constant when it counts down or up to a local that
+    /// only ever holds constants: `while k > 1: k //= 2` after `k = 8`.
+    pub fn fold_constants(&self, bound: LoopBound) -> LoopBound {
+        match bound.source() {
+            Some(SizeSource::Value(name)) if self.constants.contains(name) => {
+                LoopBound::Constant(None)
+            }
+            _ => bound,
+        }
+    }
+
+    /// Takes the variables in `assumptions` to be bounded by constants.
+    pub fn assume(&mut self, assumptions: &[Assumption]) {
+        self.assumptions.extend_from_slice(assumptions);
+    }
+
+    /// The assumption bounding what `symbol` measures, if any. An
+    /// assumption names the variable as written: `n`, `rows` or
+    /// `len(rows)`, or an alias such as `n` for `n = len(rows)`.
+    pub fn assumption(&self, symbol: &str) -> Option<&Assumption> {
+        let source = self.source(symbol)?;
+        self.assumptions.iter().find(|assumption| {
+            assumption.variable == source.name()
+                || assumption.variable == source.describe()
+                || self.aliases.get(&assumption.variable) == Some(source)
+        })
+    }
+
+    /// `source` after following local aliases: `len(arr)` for `n` after
+    /// `n = len(arr)`.
+    pub fn resolve(&self, source: &SizeSource) -> SizeSource {
+        match source {
+            SizeSource::Value(name) => self
+                .aliases
+                .get(name)
+                .cloned()
+                .unwrap_or_else(|| source.clone()),
+            SizeSource::Length(_) => source.clone(),
+        }
+    }
+
+    /// The variable standing for `source`, allocating one if needed.
+    pub fn symbol_for(&mut self, source: &SizeSource) -> String {
+        let source = self.resolve(source);
+        if let Some((_, symbol)) = self.assigned.iter().find(|(s, _)| *s == source) {
+            return symbol.clone();
+        }
+
+        let used = |symbol: &str| self.assigned.iter().any(|(_, s)| s == symbol);
+        let name = source.name();
+        let lower = name.to_lowercase();
+        let preferred = if lower.contains("edges") {
+            Some("E".to_string())
+        } else if lower.contains("vertices") || lower.ends_with("nodes") {
+            Some("V".to_string())
+        } else if matches!(source, SizeSource::Value(_)) && name.chars().count() == 1 {
+            Some(name.to_string())
+        } else {
+            None
+        };
+
+        let symbol = preferred
+            .filter(|s| !used(s))
+            .or_else(|| SYMBOLS.iter().find(|s| !used(s)).map(|s| s.to_string()))
+            .unwrap_or_else(|| format!("n{}", self.assigned.len()));
+        self.assigned.push((source, symbol.clone()));
+        symbol
+    }
+
+    /// The variable used for loops whose bound could not be read.
+    pub fn primary(&self) -> String {
+        self.assigned
+            .first()
+            .map_or_else(|| SYMBOLS[0].to_string(), |(_, s)| s.clone())
+    }
+
+    /// What the variable `symbol` measures.
+    pub fn source(&self, symbol: &str) -> Option<&SizeSource> {
+        self.assigned
+            .iter()
+            .find(|(_, s)| s == symbol)
+            .map(|(source, _)| source)
+    }
+
+    /// Maps each variable to what it measures, e.g. `n` to `len(rows)`.
+    pub fn legend(&self) -> BTreeMap<String, String> {
+        self.assigned
+            .iter()
+            .map(|(source, symbol)| (symbol.clone(), source.describe()))
+            .collect()
+    }
+}
+
+/// Parameter names declared in a function signature, in order.
+pub fn parameters(signature: &[Token]) -> Vec<String> {
+    let Some(open) = signature.iter().position(|t| t.is_punct("(")) else {
+        return Vec::new();
+    };
+    call_arguments(signature, open)
+        .into_iter()
+        .filter_map(|parameter| {
+            // `rows: &[i32]`, `rows=None`, `int[] rows`, `const vector<int>& rows`
+            let end = parameter
+                .iter()
+                .position(|t| t.is_punct(":") || t.is_punct("="))
+                .unwrap_or(parameter.len());
+            parameter[..end]
+                .iter()
+                .rev()
+                .find(|t| t.kind == TokenKind::Identifier)
+                .map(|t| t.text.clone())
+        })
+        .collect()
+}
+
+/// Locals of `function` assigned a literal and otherwise only brought down
+/// from there, like `k` in `k = 8` and `k //= 2`, or never changed, like
+/// `xs` in `xs = [1, 2, 3]`. Locals that grow, like
+/// `i` in `i = 0` and `i += 1`, are not constant.
+fn constant_locals(tokens: &[Token], function: &Block, parameters: &[String]) -> Vec<String> {
+    let body: Vec<Token> = function
+        .own_token_indices()
+        .into_iter()
+        .filter(|i| !function.header.contains(i))
+        .map(|i| tokens[i].clone())
+        .collect();
+    let mut literal: Vec<String> = Vec::new();
+    let mut varying: Vec<String> = Vec::new();
+    for (i, token) in body.iter().enumerate() {
+        // `i++`, and methods such as `xs.append(x)` that may change it
+        let stepped = |t: Option<&Token>| t.is_some_and(|t| t.is_punct("++"));
+        if token.kind == TokenKind::Identifier
+            && (stepped(body.get(i + 1))
+                || stepped(i.checked_sub(1).map(|p| &body[p]))
+                || body.get(i + 1).is_some_and(|t| t.is_punct(".")))
+        {
+            varying.push(token.text.clone());
+        }
+        let assignment = body.get(i + 1).filter(|op| {
+            op.kind == TokenKind::Punctuation
+                && op.text.ends_with('=')
+                && !matches!(
+                    op.text.as_str(),
+                    "==" | "===" | "!=" | "!==" | "<=" | ">=" | "=>"
+                )
+        });
+        let Some(op) = assignment else {
+            continue;
+        };
+        if token.kind != TokenKind::Identifier || parameters.contains(&token.text) {
+            continue;
+        }
+        let rhs: Vec<Token> = body[i + 2..]
+            .iter()
+            .take_while(|t| t.line == token.line && !t.is_punct(";"))
+            .cloned()
+            .collect();
+        let grows = GROWING_ASSIGNMENTS.contains(&op.text.as_str())
+            || op.is_punct("=")
+                && rhs.iter().any(|t| t.is_ident(&token.text))
+                && rhs.iter().any(|t| {
+                    t.kind == TokenKind::Punctuation && GROWING_OPERATORS.contains(&t.text.as_str())
+                });
+        if grows
+            || rhs
+                .iter()
+                .any(|t| t.kind == TokenKind::Identifier && t.text != token.text)
+        {
+            varying.push(token.text.clone());
+        } else if op.is_punct("=") && is_constant_value(&rhs) && !literal.contains(&token.text) {
+            literal.push(token.text.clone());
+        }
+    }
+    literal.retain(|name| !varying.contains(name));
+    literal
+}
+
+/// Position of the first parameter that `arguments` fill: 1 for a method
+/// declaring `self` or `this`, which it is called without, otherwise 0.
+pub fn argument_offset(parameters: &[String], arguments: &[&[Token]]) -> usize {
+    match parameters.first().map(String::as_str) {
+        Some("self" | "this") if parameters.len() == arguments.len() + 1 => 1,
+        _ => 0,
+    }
+}
+
+/// Loops under `block` in source order, skipping nested functions.
+fn loops(block: &Block) -> Vec<&Block> {
+    let mut found = Vec::new();
+    for child in &block.children {
+        if child.is_function() {
+            continue;
+        }
+        if child.kind == BlockKind::Loop {
+            found.push(child);
+        }
+        found.extend(loops(child));
+    }
+    found
+}
+
+/// The index a loop advances: `i` in `for (int i = 0; ...)` or
+/// `for i in range(n)`, or the condition variable a `while` loop updates.
+pub fn loop_index(tokens: &[Token], block: &Block) -> Option<String> {
+    let header = loop_header(tokens, block);
+
+    let clauses = split_top_level(header, ";");
+    if clauses.len() == 3 {
+        let (init, update) = (clauses[0], clauses[2]);
+        return match init.iter().position(|t| t.is_punct("=")) {
+            Some(eq) => eq.checked_sub(1).map(|i| init[i].text.clone()),
+            None => update
+                .iter()
+                .find(|t| t.kind == TokenKind::Identifier)
+                .map(|t| t.text.clone()),
+        };
+    }
+
+    let iterable = header.iter().position(|t| {
+        t.is_keyword("in") || t.is_keyword("of") || t.is_ident("of") || t.is_punct(":")
+    });
+    let is_for = tokens[block.span.clone()]
+        .iter()
+        .find(|t| t.kind == TokenKind::Keyword)
+        .is_some_and(|t| t.text == "for");
+    if let (Some(position), true) = (iterable, is_for) {
+        // `for i, x in ...` names the index first, `for (int x : xs)` last
+        let target = strip_parens(&header[..position]);
+        let target = split_top_level(target, ",")[0];
+        return target
+            .iter()
+            .rev()
+            .find(|t| t.kind == TokenKind::Identifier)
+            .map(|t| t.text.clone());
+    }
+
+    let first_clause = split_top_level(header, "&&")[0];
+    let body = body_tokens(tokens, block);
+    let variables = condition_variables(first_clause);
+    variables
+        .iter()
+        .find(|variable| step_of(&body, variable) == Some(Step::Multiplicative))
+        .or_else(|| {
+            variables
+                .iter()
+                .find(|variable| step_of(&body, variable).is_some())
+        })
+        .map(|variable| variable.to_string())
+        .or_else(|| counter(&body))
+}
+
+/// A variable stepped by one in `tokens`: `left += 1`, `j++`.
+fn counter(tokens: &[Token]) -> Option<String> {
+    tokens.windows(3).find_map(|window| match window {
+        [name, op, one]
+            if name.kind == TokenKind::Identifier
+                && (op.is_punct("+=") || op.is_punct("-="))
+                && one.text == "1" =>
+        {
+            Some(name.text.clone())
+        }
+        [name, op, _]
+            if name.kind == TokenKind::Identifier && (op.is_punct("++") || op.is_punct("--")) =>
+        {
+            Some(name.text.clone())
+        }
+        _ => None,
+    })
+}
+
+/// Whether the loop `block` walks the elements of a collection
+/// (`for child in node.children`) rather than a range of numbers.
+pub fn iterates_elements(tokens: &[Token], block: &Block) -> bool {
+    let header = loop_header(tokens, block);
+    if split_top_level(header, ";").len() == 3 {
+        return false;
+    }
+    let is_for = tokens[block.span.clone()]
+        .iter()
+        .find(|t| t.kind == TokenKind::Keyword)
+        .is_some_and(|t| t.text == "for");
+    let Some(position) = header.iter().position(|t| {
+        t.is_keyword("in") || t.is_keyword("of") || t.is_ident("of") || t.is_punct(":")
+    }) else {
+        return false;
+    };
+    let iterable = strip_parens(&header[position + 1..]);
+    is_for
+        && !iterable
+            .first()
+            .is_some_and(|t| t.is_ident("range") || t.is_ident("xrange"))
+        && !iterable
+            .iter()
+            .any(|t| t.is_punct("..") || t.is_punct("..="))
+}
+
+/// Whether `block` starts from wherever its index was left, as in a
+/// `while` loop or `for (; j < n; j++)`.
+pub fn continues_index(tokens: &[Token], block: &Block) -> bool {
+    let header = loop_header(tokens, block);
+    let clauses = split_top_level(header, ";");
+    if clauses.len() == 3 {
+        return clauses[0].is_empty();
+    }
+    let keyword = tokens[block.span.clone()]
+        .iter()
+        .find(|t| t.kind == TokenKind::Keyword)
+        .map(|t| t.text.as_str());
+    matches!(keyword, Some("while") | Some("do"))
+}
+
+/// Methods removing one element from a stack or queue.
+const POPPING_METHODS: &[&str] = &[
+    "pop",
+    "popleft",
+    "pop_back",
+    "pop_front",
+    "poll",
+    "pollFirst",
+    "pollLast",
+    "removeFirst",
+    "removeLast",
+    "shift",
+];
+
+/// What a `while` loop consumes on each iteration, carried over between
+/// runs of the loop.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pointer {
+    /// `left += 1`, `a[lo++]`: an index that only moves one way.
+    Advances(String),
+    /// `stack.pop()` on a collection tested in the condition.
+    Pops(String),
+}
+
+impl Pointer {
+    pub fn name(&self) -> &str {
+        match self {
+            Pointer::Advances(name) | Pointer::Pops(name) => name,
+        }
+    }
+}
+
+/// The pointer a `while` loop moves forward or the collection it drains,
+/// as in a sliding window or a monotonic stack.
+pub fn monotonic_pointer(tokens: &[Token], block: &Block) -> Option<Pointer> {
+    let keyword = tokens[block.span.clone()]
+        .iter()
+        .find(|t| t.kind == TokenKind::Keyword)?;
+    if keyword.text != "while" {
+        return None;
+    }
+    let body = body_tokens(tokens, block);
+    if let Some(name) = counter(&body) {
+        return Some(Pointer::Advances(name));
+    }
+    let header = loop_header(tokens, block);
+    body.windows(4).find_map(|window| match window {
+        [name, dot, method, open]
+            if name.kind == TokenKind::Identifier
+                && dot.is_punct(".")
+                && POPPING_METHODS.contains(&method.text.as_str())
+                && open.is_punct("(")
+                && header.iter().any(|t| t.is_ident(&name.text)) =>
+        {
+            Some(Pointer::Pops(name.text.clone()))
+        }
+        _ => None,
+    })
+}
+
+/// Whether `variable` is assigned in `outer`'s header or body outside of
+/// the nested loop `inner`.
+pub fn is_reset(tokens: &[Token], outer: &Block, inner: &Block, variable: &str) -> bool {
+    (outer.span.start..outer.span.end)
+        .filter(|i| !inner.span.contains(i))
+        .any(|i| tokens[i].is_ident(variable) && tokens.get(i + 1).is_some_and(|t| t.is_punct("=")))
+}
+
+/// Allocates variables for every loop in `function`, parameters first so
+/// that `f(rows, cols)` reads as `n = len(rows)`, `m = len(cols)`.
+pub fn assign_symbols(tokens: &[Token], function: &Block, symbols: &mut Symbols) {
+    let signature = &tokens[function.span.start..function.header.end.max(function.span.start)];
+    let parameters = parameters(signature);
+    symbols.constants = constant_locals(tokens, function, &parameters);
+    let loops = loops(function);
+    let indices: Vec<String> = loops
+        .iter()
+        .filter_map(|block| loop_index(tokens, block))
+        .collect();
+    let mut sources: Vec<SizeSource> = loops
+        .into_iter()
+        .filter_map(|block| {
+            symbols
+                .fold_constants(loop_bound(tokens, block))
+                .source()
+                .cloned()
+        })
+        // Bounds on an outer index take that loop's variable
+        .filter(|source| !matches!(source, SizeSource::Value(name) if indices.contains(name)))
+        .map(|source| symbols.resolve(&source))
+        .collect();
+    let rank = |source: &SizeSource| {
+        let name = source.name();
+        let base = name.split(['[', '.']).next().unwrap_or(name);
+        parameters
+            .iter()
+            .position(|p| p == base)
+            .unwrap_or(parameters.len())
+    };
+    sources.sort_by_key(|source| rank(source));
+    for source in &sources {
+        symbols.symbol_for(source);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::complexity::Complexity;
+    use crate::{blocks, cost, lexer};
+
+    /// The cost of the first function in `code`.
+    fn cost(code: &str, language: &str) -> Complexity {
+        let tokens: Vec<Token> = lexer::tokenize(code, language)
+            .into_iter()
+            .filter(|t| !t.is_trivia())
+            .collect();
+        let tree = blocks::build_block_tree(&tokens, language);
+        let function = tree.functions()[0];
+        let mut symbols = Symbols::with_aliases(&tokens[function.span.clone()]);
+        assign_symbols(&tokens, function, &mut symbols);
+        cost::cost_tree(&tokens, function, &mut symbols, language, &mut Vec::new())
+            .cost()
+            .clone()
+    }
+
+    #[test]
+    fn ranges_need_both_ends_constant() {
+        let countdown = r#"
+def countdown(n):
+    for i in range(n, 0, -1):
+        print(i)
+"#;
+        assert_eq!(cost(countdown, "python"), Complexity::linear());
+
+        let fixed = r#"
+def fixed(n):
+    for i in range(2, 10):
+        print(i)
+"#;
+        assert_eq!(cost(fixed, "python"), Complexity::constant());
+    }
+
+    #[test]
+    fn halving_a_constant_local_is_constant() {
+        let python = r#"
+def window(xs):
+    for x in xs:
+        k = 8
+        while k > 1:
+            k //= 2
+"#;
+        assert_eq!(cost(python, "python"), Complexity::linear());
+
+        let growing = r#"
+def steps(n):
+    i = 0
+    while i < n:
+        j = 0
+        while j < i:
+            j += 1
+        i += 1
+"#;
+        assert_eq!(cost(growing, "python"), Complexity::quadratic());
+    }
+
+    #[test]
+    fn window_offset_by_outer_index_is_constant() {
+        let window = r#"
+def window(n):
+    for i in range(n):
+        for j in range(i, i + 3):
+            print(j)
+"#;
+        assert_eq!(cost(window, "python"), Complexity::linear());
+
+        let triangle = r#"
+def triangle(n):
+    for i in range(n):
+        for j in range(i, n):
+            print(j)
+"#;
+        assert_eq!(cost(triangle, "python"), Complexity::quadratic());
+    }
+}
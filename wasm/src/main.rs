@@ -0,0 +1,342 @@
+//! `big-o-analyser`: analyzes files and directories from the command line.
+//!
+//! Walks every path given, picks the language of each file from its
+//! extension and prints the complexity of every function as a table, as
+//! JSON with `--json`, or as SARIF with `--sarif`. With `--budget` or
+//! `--max-complexity`, exits with status 1 when a function goes over its
+//! bound; with `--baseline`, only when a function got slower than in the
+//! baseline or is new and over budget.
+
+use big_o_analyser::{
+    AnalysisResult, Baseline, Budget, Complexity, ComplexityAnalyzer, language_for_path, to_sarif,
+};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: big-o-analyser [--json | --sarif] [--budget <file>] [--baseline <file>] [--language <language>] <path>...
+
+Analyzes the time and space complexity of every function in the given files
+and directories. Directories are searched recursively.
+
+Options:
+  --json                 Print the full analysis as JSON
+  --sarif                Print the functions over budget as a SARIF 2.1 log
+  --budget <file>        Check every function against the JSON budget in
+                         <file>, and exit with status 1 if any is over
+  --max-complexity <complexity>
+                         Bound of the functions the budget does not
+                         override, O(n log n) by default; exit with
+                         status 1 if any is over
+  --save-baseline <file> Record the complexity of every function in <file>
+  --baseline <file>      Only fail on functions slower than in the baseline
+                         saved in <file>, or new and over budget
+  --language <language>  Analyze every file as <language> instead of
+                         detecting it from the extension
+  -h, --help             Print this help";
+
+/// Directories that hold dependencies or build output rather than sources.
+const SKIPPED_DIRECTORIES: &[&str] = &["node_modules", "target", "build", "dist", "out"];
+
+/// Bound of every function unless a budget or `--max-complexity` says
+/// otherwise.
+const DEFAULT_MAX_COMPLEXITY: &str = "O(n log n)";
+
+/// How the analysis is printed.
+enum Format {
+    Table,
+    Json,
+    Sarif,
+}
+
+/// Options read from the command line.
+struct Options {
+    format: Format,
+    budget: Option<PathBuf>,
+    max_complexity: Option<Complexity>,
+    baseline: Option<PathBuf>,
+    save_baseline: Option<PathBuf>,
+    language: Option<String>,
+    paths: Vec<PathBuf>,
+}
+
+#[derive(Serialize)]
+struct FileReport {
+    path: String,
+    result: AnalysisResult,
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut budget = match &options.budget {
+        Some(path) => match read_json(path) {
+            Ok(budget) => budget,
+            Err(message) => {
+                eprintln!("error: {}: {}", path.display(), message);
+                return ExitCode::from(2);
+            }
+        },
+        None => Budget::new(
+            DEFAULT_MAX_COMPLEXITY
+                .parse()
+                .expect("the default maximum parses"),
+        ),
+    };
+    if let Some(max) = options.max_complexity.clone() {
+        budget.set_max(max);
+    }
+
+    let baseline: Option<Baseline> = match &options.baseline {
+        Some(path) => match read_json(path) {
+            Ok(baseline) => Some(baseline),
+            Err(message) => {
+                eprintln!("error: {}: {}", path.display(), message);
+                return ExitCode::from(2);
+            }
+        },
+        None => None,
+    };
+
+    let mut files = Vec::new();
+    let mut failed = false;
+    for path in &options.paths {
+        if let Err(error) = collect_files(path, options.language.is_some(), &mut files) {
+            eprintln!("error: {}: {}", path.display(), error);
+            failed = true;
+        }
+    }
+
+    let mut reports = Vec::with_capacity(files.len());
+    for path in files {
+        let Some(language) = options
+            .language
+            .clone()
+            .or_else(|| language_for_path(&path).map(str::to_string))
+        else {
+            continue;
+        };
+        let code = match fs::read_to_string(&path) {
+            Ok(code) => code,
+            Err(error) => {
+                eprintln!("error: {}: {}", path.display(), error);
+                failed = true;
+                continue;
+            }
+        };
+        reports.push(FileReport {
+            path: path.display().to_string(),
+            result: ComplexityAnalyzer::new(&language).analyze(&code),
+        });
+    }
+
+    let results: Vec<(&str, &AnalysisResult)> = reports
+        .iter()
+        .map(|report| (report.path.as_str(), &report.result))
+        .collect();
+    let json = match options.format {
+        Format::Table => {
+            print_table(&reports);
+            None
+        }
+        Format::Json => Some(serde_json::to_string_pretty(&reports)),
+        Format::Sarif => Some(serde_json::to_string_pretty(&to_sarif(&results, &budget))),
+    };
+    match json {
+        Some(Ok(json)) => println!("{json}"),
+        Some(Err(error)) => {
+            eprintln!("error: failed to serialize the analysis: {error}");
+            return ExitCode::FAILURE;
+        }
+        None => {}
+    }
+
+    if let Some(path) = &options.save_baseline {
+        let saved = serde_json::to_string_pretty(&Baseline::snapshot(&results))
+            .map_err(|error| error.to_string())
+            .and_then(|json| fs::write(path, json + "\n").map_err(|error| error.to_string()));
+        if let Err(message) = saved {
+            eprintln!("error: {}: {}", path.display(), message);
+            failed = true;
+        }
+    }
+
+    if let Some(baseline) = &baseline {
+        let report = baseline.compare(&results, &budget);
+        for regression in report.regressions() {
+            eprintln!("regression: {}", regression);
+        }
+        if !report.passed() {
+            eprintln!(
+                "{} of {} functions regressed",
+                report.regressions().len(),
+                report.checked()
+            );
+            failed = true;
+        }
+    } else if options.budget.is_some() || options.max_complexity.is_some() {
+        let report = budget.evaluate(&results);
+        for violation in report.violations() {
+            eprintln!("over budget: {}", violation);
+        }
+        if !report.passed() {
+            eprintln!(
+                "{} of {} functions over budget",
+                report.violations().len(),
+                report.checked()
+            );
+            failed = true;
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Reads the options, or `None` when help was asked for.
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut options = Options {
+        format: Format::Table,
+        budget: None,
+        max_complexity: None,
+        baseline: None,
+        save_baseline: None,
+        language: None,
+        paths: Vec::new(),
+    };
+    let mut args = args.peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--json" => options.format = Format::Json,
+            "--sarif" => options.format = Format::Sarif,
+            "--max-complexity" => match args.next() {
+                Some(bound) => {
+                    let bound = bound
+                        .parse()
+                        .map_err(|error| format!("--max-complexity: {error}"))?;
+                    options.max_complexity = Some(bound);
+                }
+                None => return Err("--max-complexity needs a value".to_string()),
+            },
+            "--budget" => match args.next() {
+                Some(path) => options.budget = Some(PathBuf::from(path)),
+                None => return Err("--budget needs a file".to_string()),
+            },
+            "--baseline" => match args.next() {
+                Some(path) => options.baseline = Some(PathBuf::from(path)),
+                None => return Err("--baseline needs a file".to_string()),
+            },
+            "--save-baseline" => match args.next() {
+                Some(path) => options.save_baseline = Some(PathBuf::from(path)),
+                None => return Err("--save-baseline needs a file".to_string()),
+            },
+            "--language" => match args.next() {
+                Some(language) => options.language = Some(language.to_lowercase()),
+                None => return Err("--language needs a value".to_string()),
+            },
+            _ if arg.starts_with('-') => return Err(format!("unknown option '{arg}'")),
+            _ => options.paths.push(PathBuf::from(arg)),
+        }
+    }
+    if options.paths.is_empty() {
+        return Err("no paths given".to_string());
+    }
+    Ok(Some(options))
+}
+
+/// Reads a budget or baseline from its JSON file.
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let text = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    serde_json::from_str(&text).map_err(|error| error.to_string())
+}
+
+/// Adds `path` to `files`, or every source file below it when it is a
+/// directory. Hidden and dependency directories are skipped; with
+/// `all_files`, files are kept whatever their extension.
+fn collect_files(path: &Path, all_files: bool, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if !path.is_dir() {
+        // Reports a missing path
+        fs::metadata(path)?;
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    entries.sort();
+    for entry in entries {
+        let name = entry
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if entry.is_dir() {
+            if !name.starts_with('.') && !SKIPPED_DIRECTORIES.contains(&name.as_str()) {
+                collect_files(&entry, all_files, files)?;
+            }
+        } else if all_files || language_for_path(&entry).is_some() {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
+
+/// One row per function: location, name, time, space and confidence.
+fn print_table(reports: &[FileReport]) {
+    let header = ["LOCATION", "FUNCTION", "TIME", "SPACE", "CONFIDENCE"];
+    let mut rows: Vec<[String; 5]> = Vec::new();
+    for report in reports {
+        for function in report.result.functions() {
+            rows.push([
+                format!("{}:{}", report.path, function.line_start()),
+                function.function().to_string(),
+                function.complexity().to_string(),
+                function.space_complexity().complexity().to_string(),
+                format!("{:.0}%", function.confidence() * 100.0),
+            ]);
+        }
+    }
+
+    let mut widths = header.map(|title| title.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let print_row = |cells: &[String]| {
+        let line: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{:width$}", cell))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    };
+
+    print_row(&header.map(str::to_string));
+    for row in &rows {
+        print_row(row);
+    }
+
+    for report in reports {
+        for warning in report.result.warnings() {
+            eprintln!("warning: {}: {}", report.path, warning);
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Memoization detection.
+//!
+//! Recognizes recursive functions whose results are cached, either by a
+//! decorator (`@lru_cache`, `@cache`) or by a lookup in a memo table that
+//! guards the recursive calls (`if n in memo`, `memo.get(n)`,
+//! `dp[i] != -1`). Such functions cost one evaluation per distinct state.
+
+use crate::blocks::Block;
+use crate::lexer::{self, Token, TokenKind};
+
+/// Decorators that cache a function's results.
+const CACHING_DECORATORS: &[&str] = &["lru_cache", "cache", "cached", "memoize", "memoized"];
+
+/// Lookups on a map or set that test for a cached entry.
+const LOOKUP_METHODS: &[&str] = &[
+    "get",
+    "has",
+    "containsKey",
+    "contains_key",
+    "contains",
+    "count",
+    "find",
+    "getOrDefault",
+    "get_or_insert",
+    "entry",
+];
+
+/// Whether `name` looks like a memo table: `memo`, `cache`, `dp`, `table`.
+fn is_memo_name(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    ["memo", "cache", "computed", "lookup"]
+        .iter()
+        .any(|fragment| lower.contains(fragment))
+        || lower == "dp"
+        || lower.starts_with("dp_")
+        || lower == "table"
+}
+
+/// Describes how `function` caches its results, if it does.
+pub fn memo_guard(tokens: &[Token], function: &Block) -> Option<String> {
+    // `@lru_cache(maxsize=None)`, `@functools.cache`
+    let header = &tokens[function.span.start..function.header.end.max(function.span.start)];
+    for (i, token) in header.iter().enumerate() {
+        if !token.is_punct("@") {
+            continue;
+        }
+        let decorator = header[i + 1..]
+            .iter()
+            .take_while(|t| t.kind == TokenKind::Identifier || t.is_punct("."))
+            .filter(|t| t.kind == TokenKind::Identifier)
+            .last();
+        if let Some(decorator) = decorator
+            && CACHING_DECORATORS.contains(&decorator.text.as_str())
+        {
+            return Some(format!("@{} decorator", decorator.text));
+        }
+    }
+
+    for i in function.header.end.max(function.span.start)..function.span.end {
+        let token = &tokens[i];
+        if token.kind != TokenKind::Identifier || !is_memo_name(&token.text) {
+            continue;
+        }
+        let previous = i.checked_sub(1).map(|p| &tokens[p]);
+        let next = tokens.get(i + 1);
+
+        // `if n in memo`, `if key not in cache`, but not `for key in cache`
+        let iterated = i >= 3 && tokens[i - 3].is_keyword("for");
+        if previous.is_some_and(|p| p.is_keyword("in")) && !iterated {
+            return Some(format!("membership test on '{}'", token.text));
+        }
+
+        // `memo.get(n)`, `cache.has(key)`, `dp.containsKey(i)`
+        if next.is_some_and(|t| t.is_punct("."))
+            && tokens
+                .get(i + 2)
+                .is_some_and(|t| LOOKUP_METHODS.contains(&t.text.as_str()))
+            && lexer::is_call(tokens, i + 2)
+        {
+            return Some(format!("'{}.{}' lookup", token.text, tokens[i + 2].text));
+        }
+
+        // `dp[i][j] != -1`, `memo[n] is not None`
+        if next.is_some_and(|t| t.is_punct("[")) {
+            let mut end = i + 1;
+            while tokens.get(end).is_some_and(|t| t.is_punct("[")) {
+                match lexer::matching_close(tokens, end) {
+                    Some(close) => end = close + 1,
+                    None => break,
+                }
+            }
+            let compared = tokens.get(end).is_some_and(|t| {
+                t.is_keyword("is")
+                    || (t.kind == TokenKind::Punctuation
+                        && matches!(t.text.as_str(), "==" | "!=" | "===" | "!==" | ">=" | "<"))
+            });
+            if compared {
+                return Some(format!("'{}[...]' check", token.text));
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,283 @@
+//! Project-wide analysis.
+//!
+//! Analyzes several files at once, so that a call into a function defined
+//! in another file is charged what that function costs. A file sees the
+//! functions of the files it imports (`from utils import helper`, `import
+//! { helper } from './utils'`, `use crate::utils::helper`), Java classes
+//! see the other classes of their directory, and C and C++ files share a
+//! single namespace.
+
+use crate::calls::Module;
+use crate::complexity::Complexity;
+use crate::lexer::{Token, TokenKind};
+use crate::{AnalysisResult, ComplexityAnalyzer, analyze_units};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// How many functions the project summary lists.
+const WORST_FUNCTIONS: usize = 10;
+
+/// Statements that import another module.
+const IMPORT_KEYWORDS: &[&str] = &["import", "from", "use", "require"];
+
+/// File names that stand for their directory: `utils/__init__.py` is
+/// imported as `utils`.
+const INDEX_FILES: &[&str] = &["__init__", "index", "mod", "lib"];
+
+/// One file of the project.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceFile {
+    pub path: String,
+    pub language: String,
+    pub source: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileAnalysis {
+    path: String,
+    result: AnalysisResult,
+}
+
+/// A function of the project, ranked by its complexity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankedFunction {
+    path: String,
+    function: String,
+    complexity: Complexity,
+    confidence: f64,
+    line_start: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectSummary {
+    /// Highest complexity of any function in the project.
+    overall: Complexity,
+    /// The most expensive functions, worst first.
+    worst_functions: Vec<RankedFunction>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectAnalysis {
+    files: Vec<FileAnalysis>,
+    summary: ProjectSummary,
+}
+
+impl FileAnalysis {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn result(&self) -> &AnalysisResult {
+        &self.result
+    }
+}
+
+impl RankedFunction {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn function(&self) -> &str {
+        &self.function
+    }
+
+    pub fn complexity(&self) -> &Complexity {
+        &self.complexity
+    }
+
+    pub fn confidence(&self) -> f64 {
+        self.confidence
+    }
+
+    pub fn line_start(&self) -> usize {
+        self.line_start
+    }
+}
+
+impl ProjectSummary {
+    pub fn overall(&self) -> &Complexity {
+        &self.overall
+    }
+
+    pub fn worst_functions(&self) -> &[RankedFunction] {
+        &self.worst_functions
+    }
+}
+
+impl ProjectAnalysis {
+    /// Analyses of the files, in the order they were given.
+    pub fn files(&self) -> &[FileAnalysis] {
+        &self.files
+    }
+
+    pub fn summary(&self) -> &ProjectSummary {
+        &self.summary
+    }
+}
+
+/// Analyzes `files` together, resolving calls between them where their
+/// imports allow it.
+pub fn analyze_sources(files: &[SourceFile]) -> ProjectAnalysis {
+    let analyzers: Vec<ComplexityAnalyzer> = files
+        .iter()
+        .map(|file| ComplexityAnalyzer::new(&file.language))
+        .collect();
+
+    // Files too large to analyze get an empty result of their own
+    let mut prepared = Vec::new();
+    let mut units = Vec::new();
+    for (position, (file, analyzer)) in files.iter().zip(&analyzers).enumerate() {
+        if let Some(unit) = analyzer.unit(&file.source) {
+            prepared.push(position);
+            units.push(unit);
+        }
+    }
+
+    let names: Vec<String> = prepared
+        .iter()
+        .map(|&position| module_name(&files[position].path))
+        .collect();
+    let modules: Vec<Module> = prepared
+        .iter()
+        .zip(&units)
+        .enumerate()
+        .map(|(index, (&position, unit))| {
+            let file = &files[position];
+            let imported = imported_names(&unit.tokens);
+            let imports = prepared
+                .iter()
+                .enumerate()
+                .filter(|&(other, &other_position)| {
+                    let candidate = &files[other_position];
+                    other != index
+                        && (imported.contains(&names[other]) || shares_namespace(file, candidate))
+                })
+                .map(|(other, _)| other)
+                .collect();
+            Module {
+                name: names[index].clone(),
+                imports,
+            }
+        })
+        .collect();
+
+    let analyses = analyze_units(&mut units, &modules);
+    let mut results: Vec<Option<AnalysisResult>> = files.iter().map(|_| None).collect();
+    for ((position, unit), analyses) in prepared.into_iter().zip(units).zip(analyses) {
+        results[position] = Some(analyzers[position].report(unit, analyses));
+    }
+
+    let files: Vec<FileAnalysis> = files
+        .iter()
+        .zip(&analyzers)
+        .zip(results)
+        .map(|((file, analyzer), result)| FileAnalysis {
+            path: file.path.clone(),
+            result: result.unwrap_or_else(|| analyzer.too_large()),
+        })
+        .collect();
+    let summary = summarize(&files);
+    ProjectAnalysis { files, summary }
+}
+
+/// Overall complexity and the worst functions across `files`.
+fn summarize(files: &[FileAnalysis]) -> ProjectSummary {
+    let mut ranked: Vec<RankedFunction> = files
+        .iter()
+        .flat_map(|file| {
+            file.result.functions.iter().map(|f| RankedFunction {
+                path: file.path.clone(),
+                function: f.function.clone(),
+                complexity: f.complexity.clone(),
+                confidence: f.confidence,
+                line_start: f.line_start,
+            })
+        })
+        .collect();
+    // Stable, so equally complex functions keep their file order
+    ranked.sort_by(|a, b| b.complexity.cmp(&a.complexity));
+    ranked.truncate(WORST_FUNCTIONS);
+
+    ProjectSummary {
+        overall: ranked
+            .first()
+            .map_or_else(Complexity::constant, |f| f.complexity.clone()),
+        worst_functions: ranked,
+    }
+}
+
+/// The name other files import `path` by: its file stem, or the directory
+/// for index files such as `__init__.py` and `mod.rs`.
+fn module_name(path: &str) -> String {
+    let path = Path::new(path);
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if INDEX_FILES.contains(&stem.as_str())
+        && let Some(directory) = path.parent().and_then(Path::file_name)
+    {
+        return directory.to_string_lossy().into_owned();
+    }
+    stem
+}
+
+/// Every name appearing in the import statements of a file, including the
+/// last segment of quoted paths: `utils` for `require('./lib/utils.js')`.
+fn imported_names(tokens: &[Token]) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = &tokens[i];
+        let starts_line = i == 0 || tokens[i - 1].line != token.line;
+        let imports = IMPORT_KEYWORDS.contains(&token.text.as_str())
+            && (starts_line || token.text == "require");
+        if !imports {
+            i += 1;
+            continue;
+        }
+
+        // The statement ends with its line, unless a `{` list spans lines
+        let mut depth = 0usize;
+        let mut line = token.line;
+        while let Some(token) = tokens.get(i) {
+            if token.line != line {
+                if depth == 0 {
+                    break;
+                }
+                line = token.line;
+            }
+            if token.is_punct("{") {
+                depth += 1;
+            } else if token.is_punct("}") {
+                depth = depth.saturating_sub(1);
+            } else if token.is_punct(";") && depth == 0 {
+                break;
+            }
+            match token.kind {
+                TokenKind::Identifier => {
+                    names.insert(token.text.clone());
+                }
+                TokenKind::String => {
+                    names.insert(module_name(token.text.trim_matches(['"', '\'', '`'])));
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+    names
+}
+
+/// Whether `file` sees `other` without importing it: C and C++ link every
+/// file together, and Java classes see the classes of their package.
+fn shares_namespace(file: &SourceFile, other: &SourceFile) -> bool {
+    let native = |language: &str| matches!(language.to_lowercase().as_str(), "c" | "cpp");
+    if native(&file.language) && native(&other.language) {
+        return true;
+    }
+    file.language.eq_ignore_ascii_case("java")
+        && other.language.eq_ignore_ascii_case("java")
+        && Path::new(&file.path).parent() == Path::new(&other.path).parent()
+}
@@ -0,0 +1,1278 @@
+//! Recurrence relations for recursive functions.
+//!
+//! Reads `T(n) = a·T(n/b) + f(n)` (or `T(n-1) + ...`) off a function body
+//! from its recursive calls, how their arguments shrink and the work done
+//! around them, then solves it with the Master Theorem, Akra–Bazzi or by
+//! unrolling.
+
+use crate::blocks::{Block, BlockKind};
+use crate::complexity::{Base, Complexity};
+use crate::lexer::{self, Token, TokenKind};
+use crate::loops::{self, LoopBound, SizeSource, Symbols};
+use std::fmt;
+
+/// How the input of a recursive call relates to the caller's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shrink {
+    /// `n/2`, `2n/3`: `mid`, `n // 2`, `arr[:mid]`, `node.left`.
+    Fraction { numerator: u32, denominator: u32 },
+    /// `n-1`, `n-2`: `n - 1`, `arr[1:]`, `i + 1`, `node.next`.
+    Subtract(u32),
+    /// One element of a collection the caller iterates over, as in
+    /// `for child in node.children: visit(child)`; the calls share the input.
+    Element,
+}
+
+impl Shrink {
+    fn ratio(&self) -> f64 {
+        match self {
+            Shrink::Fraction {
+                numerator,
+                denominator,
+            } => f64::from(*numerator) / f64::from(*denominator),
+            Shrink::Subtract(_) | Shrink::Element => 1.0,
+        }
+    }
+}
+
+impl fmt::Display for Shrink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Shrink::Fraction {
+                numerator: 1,
+                denominator,
+            } => write!(f, "n/{}", denominator),
+            Shrink::Fraction {
+                numerator,
+                denominator,
+            } => write!(f, "{}n/{}", numerator, denominator),
+            Shrink::Subtract(c) => write!(f, "n-{}", c),
+            Shrink::Element => write!(f, "nᵢ"),
+        }
+    }
+}
+
+/// How many times a recursive call runs per invocation of the function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fanout {
+    Once,
+    /// Inside loops with a known count: `for d in range(4)`.
+    Times(u64),
+    /// Inside a loop over an input: `for c in choices`.
+    Symbol(String),
+}
+
+impl Fanout {
+    fn times(&self, other: &Fanout) -> Fanout {
+        match (self, other) {
+            (Fanout::Once, other) | (other, Fanout::Once) => other.clone(),
+            (Fanout::Times(a), Fanout::Times(b)) => Fanout::Times(a.saturating_mul(*b)),
+            (Fanout::Symbol(s), _) | (_, Fanout::Symbol(s)) => Fanout::Symbol(s.clone()),
+        }
+    }
+}
+
+/// One recursive call site.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Call {
+    pub shrink: Shrink,
+    pub fanout: Fanout,
+    /// Whether the argument is derived from the index of the loop around the
+    /// call (`bt(i + 1)`, `f(n - i)`), so later choices skip earlier ones.
+    pub follows_loop: bool,
+}
+
+/// `T(n) = Σ T(shrink) + work`
+#[derive(Debug, Clone)]
+pub struct Recurrence {
+    variable: String,
+    calls: Vec<Call>,
+    work: Complexity,
+    /// Calls whose argument did not visibly shrink and were taken as `n-1`.
+    assumed: usize,
+    /// Parameters that change between calls; with memoization each
+    /// combination of their values is evaluated once.
+    states: Vec<SizeSource>,
+}
+
+/// Closed form of a recurrence and how it was obtained.
+#[derive(Debug, Clone)]
+pub struct Solution {
+    pub complexity: Complexity,
+    pub derivation: String,
+}
+
+impl Recurrence {
+    /// Number of calls whose shrink had to be assumed.
+    pub fn assumed(&self) -> usize {
+        self.assumed
+    }
+
+    /// Work done by one invocation outside its recursive calls.
+    pub fn work(&self) -> &Complexity {
+        &self.work
+    }
+
+    /// Whether a single invocation can recurse more than once.
+    pub fn is_branching(&self) -> bool {
+        self.calls.len() > 1 || self.calls.iter().any(|call| call.fanout != Fanout::Once)
+    }
+
+    /// Whether every call works on a fraction of the input.
+    pub fn is_divide_and_conquer(&self) -> bool {
+        self.calls
+            .iter()
+            .all(|call| matches!(call.shrink, Shrink::Fraction { .. }))
+    }
+
+    /// The same recursion when a pivot splits the input as unevenly as
+    /// possible: one call on all but one element, the others on nothing.
+    pub fn unbalanced(&self) -> Recurrence {
+        Recurrence {
+            variable: self.variable.clone(),
+            calls: vec![Call {
+                shrink: Shrink::Subtract(1),
+                fanout: Fanout::Once,
+                follows_loop: false,
+            }],
+            work: self.work.clone(),
+            assumed: 0,
+            states: self.states.clone(),
+        }
+    }
+
+    /// Shrinks with every call site repeated as often as it runs.
+    fn expanded(&self) -> Vec<Shrink> {
+        self.calls
+            .iter()
+            .flat_map(|call| {
+                let count = match call.fanout {
+                    Fanout::Times(count) => count.min(64) as usize,
+                    Fanout::Once | Fanout::Symbol(_) => 1,
+                };
+                std::iter::repeat_n(call.shrink, count)
+            })
+            .collect()
+    }
+
+    /// Number of distinct argument combinations the calls can reach.
+    pub fn states(&self, symbols: &mut Symbols) -> Complexity {
+        let mut variables: Vec<String> = Vec::new();
+        for source in &self.states {
+            let symbol = symbols.symbol_for(source);
+            if !variables.contains(&symbol) {
+                variables.push(symbol);
+            }
+        }
+        if variables.is_empty() {
+            variables.push(self.variable.clone());
+        }
+        variables.iter().fold(Complexity::constant(), |states, v| {
+            states.times(&Complexity::variable(v))
+        })
+    }
+
+    /// Deepest chain of nested calls: `log n` when every call divides the
+    /// input, otherwise `n`.
+    pub fn depth(&self) -> Complexity {
+        let halving = self
+            .calls
+            .iter()
+            .all(|call| matches!(call.shrink, Shrink::Fraction { .. }));
+        if halving {
+            Complexity::log_of(&self.variable)
+        } else {
+            Complexity::variable(&self.variable)
+        }
+    }
+
+    /// Bound once every distinct state is evaluated a single time: the
+    /// number of states times the work done per state.
+    pub fn memoized(&self, per_state: &Complexity, symbols: &mut Symbols) -> Solution {
+        let states = self.states(symbols);
+        let complexity = states.times(per_state);
+        Solution {
+            derivation: format!(
+                "Memoized: {states} distinct states × {per_state} work per state = {complexity}"
+            ),
+            complexity,
+        }
+    }
+
+    pub fn solve(&self) -> Solution {
+        let n = self.variable.as_str();
+        if self.calls.iter().any(|call| call.shrink == Shrink::Element) {
+            return Solution {
+                complexity: Complexity::variable(n),
+                derivation: format!(
+                    "Each call handles one element of the collection being iterated, \
+                     so every element of the input is visited once: O({n})"
+                ),
+            };
+        }
+        if self.is_divide_and_conquer() {
+            return self.solve_divide_and_conquer();
+        }
+
+        let expanded = self.expanded();
+        if let (
+            [Shrink::Subtract(c)],
+            [
+                Call {
+                    fanout: Fanout::Once,
+                    ..
+                },
+            ],
+        ) = (expanded.as_slice(), self.calls.as_slice())
+        {
+            // T(n) = T(n-c) + f(n) unrolls to n/c levels of f(n)
+            let levels = match c {
+                1 => n.to_string(),
+                c => format!("{n}/{c}"),
+            };
+            return Solution {
+                complexity: Complexity::variable(n).times(&self.work),
+                derivation: format!(
+                    "Unrolling T({n}) = T({n}-{c}) + f({n}) gives {levels} levels of f({n})"
+                ),
+            };
+        }
+
+        // The recursion tree is `depth` levels deep with `branching` children
+        // per node; the shallowest call bounds the depth.
+        let decrements: Vec<u32> = expanded
+            .iter()
+            .map(|shrink| match shrink {
+                Shrink::Subtract(c) => *c,
+                _ => 1,
+            })
+            .collect();
+        let decrement = decrements.iter().copied().min().unwrap_or(1).max(1);
+        let depth = match decrement {
+            1 => n.to_string(),
+            c => format!("{n}/{c}"),
+        };
+
+        let symbolic = self.calls.iter().find_map(|call| match &call.fanout {
+            Fanout::Symbol(symbol) => Some((symbol.clone(), call.follows_loop)),
+            _ => None,
+        });
+        let (growth, derivation) = match symbolic {
+            Some((symbol, true)) if symbol == n => (
+                Complexity::exponential_of(Base::Number(2), n, 1),
+                format!(
+                    "Each call loops over the remaining {n} choices and continues after \
+                     the chosen one, so every subset is generated once: 2^{n} calls"
+                ),
+            ),
+            Some((symbol, false)) if symbol == n => (
+                Complexity::factorial_of(n),
+                format!(
+                    "Each call loops over {n} choices and recurses on one fewer: \
+                     {n}·({n}-1)·…·1 = {n}! calls"
+                ),
+            ),
+            Some((symbol, _)) => (
+                Complexity::exponential_of(Base::Symbol(symbol.clone()), n, decrement),
+                format!(
+                    "Branching factor {symbol} (a loop over {symbol} choices) over a \
+                     recursion depth of {depth}: {}",
+                    Complexity::exponential_of(Base::Symbol(symbol.clone()), n, decrement)
+                ),
+            ),
+            None => {
+                let branching = expanded.len() as u64;
+                let growth = Complexity::exponential_of(Base::Number(branching), n, decrement);
+                let mut derivation = format!(
+                    "Branching factor {} over a recursion depth of {}: {}",
+                    branching, depth, growth
+                );
+                // Σ x^(-cᵢ) = 1 gives the exact growth rate when the calls
+                // shrink by different amounts, as in fibonacci
+                if decrements.iter().any(|&c| c != decrement) {
+                    let root = characteristic_root(&decrements);
+                    derivation.push_str(&format!(" (tight bound ≈ {:.2}^{})", root, n));
+                }
+                (growth, derivation)
+            }
+        };
+
+        Solution {
+            complexity: growth.times(&self.work),
+            derivation,
+        }
+    }
+
+    fn solve_divide_and_conquer(&self) -> Solution {
+        let n = self.variable.as_str();
+        let calls = self.expanded();
+        let a = calls.len();
+        let first = calls[0];
+        let uniform = calls.iter().all(|call| *call == first)
+            && matches!(first, Shrink::Fraction { numerator: 1, .. });
+
+        // Critical exponent: log_b(a), or the p with Σ rᵢ^p = 1
+        let (critical, method) = if uniform {
+            let b = 1.0 / first.ratio();
+            let critical = (a as f64).ln() / b.ln();
+            (
+                critical,
+                format!(
+                    "{n}^log_{}({}) = {n}^{}",
+                    b.round(),
+                    a,
+                    format_exponent(critical)
+                ),
+            )
+        } else {
+            let critical = akra_bazzi_exponent(&self.expanded());
+            (
+                critical,
+                format!("{n}^p with p = {}", format_exponent(critical)),
+            )
+        };
+        let leading = |extra_logs: u32| {
+            let rounded = critical.ceil() as u32;
+            let mut complexity = Complexity::power(n, rounded);
+            for _ in 0..extra_logs {
+                complexity = complexity.times(&Complexity::log_of(n));
+            }
+            complexity
+        };
+        let approximation = if (critical - critical.round()).abs() > 1e-9 {
+            ", rounded up to the next integer power"
+        } else {
+            ""
+        };
+
+        let theorem = if uniform {
+            "Master Theorem"
+        } else {
+            "Akra–Bazzi"
+        };
+        let Some((power, logs)) = self.work.polylog_degree(n) else {
+            return Solution {
+                complexity: self.work.clone(),
+                derivation: format!("{}: f({}) = {} dominates", theorem, n, self.work),
+            };
+        };
+
+        let power = f64::from(power);
+        if (power - critical).abs() < 1e-9 {
+            Solution {
+                complexity: leading(logs + 1).plus(&self.work),
+                derivation: format!(
+                    "{} case 2: f({}) = {} matches {}, adding a log factor",
+                    theorem, n, self.work, method
+                ),
+            }
+        } else if power > critical {
+            Solution {
+                complexity: self.work.clone(),
+                derivation: format!(
+                    "{} case 3: f({}) = {} dominates {}",
+                    theorem, n, self.work, method
+                ),
+            }
+        } else {
+            Solution {
+                complexity: leading(0).plus(&self.work),
+                derivation: format!(
+                    "{} case 1: f({}) = {} is dominated by {}{}",
+                    theorem, n, self.work, method, approximation
+                ),
+            }
+        }
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let n = &self.variable;
+        let mut groups: Vec<(Shrink, u64, Option<&str>)> = Vec::new();
+        for call in &self.calls {
+            let (count, symbol) = match &call.fanout {
+                Fanout::Once => (1, None),
+                Fanout::Times(count) => (*count, None),
+                Fanout::Symbol(symbol) => (1, Some(symbol.as_str())),
+            };
+            match groups
+                .iter_mut()
+                .find(|(shrink, _, s)| *shrink == call.shrink && *s == symbol)
+            {
+                Some((_, total, _)) => *total += count,
+                None => groups.push((call.shrink, count, symbol)),
+            }
+        }
+        let calls: Vec<String> = groups
+            .iter()
+            .map(|(shrink, count, symbol)| {
+                let argument = shrink.to_string().replace('n', n);
+                let term = match shrink {
+                    Shrink::Element => format!("Σ T({})", argument),
+                    _ => format!("T({})", argument),
+                };
+                match (count, symbol) {
+                    _ if *shrink == Shrink::Element => term,
+                    (1, None) => term,
+                    (count, None) => format!("{}{}", count, term),
+                    (1, Some(symbol)) => format!("{}·{}", symbol, term),
+                    (count, Some(symbol)) => format!("{}{}·{}", count, symbol, term),
+                }
+            })
+            .collect();
+        write!(f, "T({}) = {} + {}", n, calls.join(" + "), self.work)
+    }
+}
+
+/// Solves `Σ x^(-cᵢ) = 1` for the growth rate `x` of `T(n) = Σ T(n - cᵢ)`.
+fn characteristic_root(decrements: &[u32]) -> f64 {
+    let sum = |x: f64| decrements.iter().map(|&c| x.powi(-(c as i32))).sum::<f64>();
+    let (mut low, mut high) = (1.0f64, decrements.len().max(2) as f64);
+    for _ in 0..100 {
+        let middle = (low + high) / 2.0;
+        if sum(middle) > 1.0 {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    (low + high) / 2.0
+}
+
+fn format_exponent(exponent: f64) -> String {
+    if (exponent - exponent.round()).abs() < 1e-9 {
+        format!("{}", exponent.round())
+    } else {
+        format!("{:.2}", exponent)
+    }
+}
+
+/// Solves `Σ rᵢ^p = 1` for `p` by bisection.
+fn akra_bazzi_exponent(calls: &[Shrink]) -> f64 {
+    let sum = |p: f64| calls.iter().map(|c| c.ratio().powf(p)).sum::<f64>();
+    let (mut low, mut high) = (0.0f64, 16.0f64);
+    for _ in 0..100 {
+        let middle = (low + high) / 2.0;
+        if sum(middle) > 1.0 {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    let p = (low + high) / 2.0;
+    // Snap values like 0.9999999 back to the integer they approximate
+    if (p - p.round()).abs() < 1e-6 {
+        p.round()
+    } else {
+        p
+    }
+}
+
+/// Helpers assumed to copy or combine their input in linear time when the
+/// call graph has not measured them.
+const LINEAR_HELPERS: &[&str] = &["merge", "partition", "combine", "concat"];
+const COPYING_CALLS: &[&str] = &["slice", "substring", "copyOfRange", "copyOf"];
+
+/// A recursive call found in the body, with the loop it sits in.
+struct Site {
+    index: usize,
+    fanout: Fanout,
+    /// Index or element variable of the innermost enclosing loop.
+    loop_variable: Option<String>,
+    /// Whether that loop walks a collection rather than a range.
+    over_collection: bool,
+}
+
+/// Builds the recurrence of `function`, whose non-recursive loops cost
+/// `loop_cost`. Calls to any of `names`, the function and those it is
+/// mutually recursive with, count as recursive; calls at the token indices
+/// in `measured` go to functions whose cost `loop_cost` already includes.
+/// Returns `None` when no recursive call is reachable.
+pub fn extract(
+    tokens: &[Token],
+    function: &Block,
+    names: &[&str],
+    measured: &[usize],
+    symbols: &mut Symbols,
+    loop_cost: &Complexity,
+    language: &str,
+) -> Option<(Recurrence, Vec<String>)> {
+    let enclosing = Site {
+        index: 0,
+        fanout: Fanout::Once,
+        loop_variable: None,
+        over_collection: false,
+    };
+    let sites = reachable_calls(tokens, function, names, &enclosing, symbols);
+    if sites.is_empty() {
+        return None;
+    }
+
+    let signature = &tokens[function.span.start..function.header.end.max(function.span.start)];
+    let parameters = loops::parameters(signature);
+    let own: Vec<usize> = function
+        .own_token_indices()
+        .into_iter()
+        .filter(|i| !function.header.contains(i))
+        .collect();
+    let indices: Vec<usize> = sites.iter().map(|site| site.index).collect();
+    let mut split = split_variables(tokens, &own, &indices, &parameters);
+    let body: Vec<Token> = own.iter().map(|&i| tokens[i].clone()).collect();
+    split.extend(loops::midpoints(&body).into_iter().map(str::to_string));
+
+    let mut assumed = 0;
+    let mut shrinking: Vec<Vec<SizeSource>> = Vec::new();
+    let mut varying: Vec<SizeSource> = Vec::new();
+    let calls: Vec<Call> = sites
+        .into_iter()
+        .map(|site| {
+            let arguments = loops::call_arguments(tokens, site.index + 1);
+            for source in state_parameters(tokens, function, &arguments, &parameters) {
+                if !varying.contains(&source) {
+                    varying.push(source);
+                }
+            }
+            shrinking.push(shrinking_parameters(
+                tokens,
+                function,
+                &arguments,
+                &parameters,
+            ));
+            let loop_variable = site.loop_variable.as_deref();
+            let follows_loop = loop_variable.is_some_and(|variable| {
+                arguments
+                    .iter()
+                    .any(|arg| arg.iter().any(|t| t.is_ident(variable)))
+            });
+            let is_element = site.over_collection
+                && arguments
+                    .iter()
+                    .any(|arg| matches!(arg, [only] if Some(only.text.as_str()) == loop_variable));
+            let shrink = if is_element {
+                Shrink::Element
+            } else {
+                call_shrink(&arguments, &split, &parameters).unwrap_or_else(|| {
+                    assumed += 1;
+                    Shrink::Subtract(1)
+                })
+            };
+            Call {
+                shrink,
+                fanout: site.fanout,
+                follows_loop,
+            }
+        })
+        .collect();
+
+    // Measure the recursion by the parameter it shrinks: `amount` in
+    // `coin(coins, amount - c)`, `arr` in `f(arr[1:])`. One that every call
+    // shrinks bounds the depth, like `i` in `knap(i + 1, w)` and
+    // `knap(i + 1, w - wt[i])`, so it wins over one only some calls shrink.
+    let first = shrinking.iter().find_map(|sources| sources.first());
+    let everywhere = shrinking.first().and_then(|sources| {
+        sources
+            .iter()
+            .find(|source| shrinking.iter().all(|other| other.contains(source)))
+    });
+    let variable = match everywhere.or(first) {
+        Some(source) => symbols.symbol_for(source),
+        None => symbols.primary(),
+    };
+
+    // Work outside the recursive calls; a loop around them is branching,
+    // already counted by the calls themselves
+    let mut notes = Vec::new();
+    let branches_in_loop = calls.iter().any(|call| call.fanout != Fanout::Once);
+    let mut work = if branches_in_loop {
+        notes.push("Loop around the recursive calls counted as branching".to_string());
+        Complexity::constant()
+    } else {
+        loop_cost.clone()
+    };
+    if let Some(helper) = own.iter().copied().find_map(|i| {
+        let t = &tokens[i];
+        (lexer::is_call(tokens, i)
+            && !measured.contains(&i)
+            && !names.contains(&t.text.as_str())
+            && LINEAR_HELPERS
+                .iter()
+                .any(|helper| t.text.eq_ignore_ascii_case(helper)))
+        .then_some(t)
+    }) {
+        notes.push(format!("'{}' assumed to take linear time", helper.text));
+        work = work.plus(&Complexity::variable(&variable));
+    }
+    if copies_input(tokens, &own, language) {
+        notes.push("Slicing copies the input in linear time".to_string());
+        work = work.plus(&Complexity::variable(&variable));
+    }
+
+    Some((
+        Recurrence {
+            variable,
+            calls,
+            work,
+            assumed,
+            states: varying,
+        },
+        notes,
+    ))
+}
+
+/// The parameters a call passes on in reduced form, in argument order:
+/// `n` for `f(n - 1)`, `len(arr)` for `f(arr[1:])`, and for an index moving
+/// towards a limit, as in `f(i + 1)` with `if i == len(a)`, that limit.
+fn shrinking_parameters(
+    tokens: &[Token],
+    function: &Block,
+    arguments: &[&[Token]],
+    parameters: &[String],
+) -> Vec<SizeSource> {
+    let offset = loops::argument_offset(parameters, arguments);
+    arguments
+        .iter()
+        .enumerate()
+        .filter_map(|(position, arg)| {
+            let parameter = parameters.get(position + offset)?;
+            let first = arg.first()?;
+            if first.text != *parameter {
+                return None;
+            }
+            // `arr[1:]`, `node.next`, but not `w - wt[i]`
+            let sliced = arg
+                .get(1)
+                .is_some_and(|t| t.is_punct("[") || t.is_punct("."));
+            match argument_shrink(arg, &[], parameters)? {
+                Shrink::Subtract(_) if sliced => Some(SizeSource::Length(parameter.clone())),
+                Shrink::Subtract(_) if arg.iter().any(|t| t.is_punct("-")) => {
+                    Some(SizeSource::Value(parameter.clone()))
+                }
+                Shrink::Subtract(_) if arg.iter().any(|t| t.is_punct("+")) => {
+                    index_bound(tokens, function, parameter)
+                }
+                Shrink::Fraction { .. } if arg.get(1).is_some_and(|t| t.is_punct("[")) => {
+                    Some(SizeSource::Length(parameter.clone()))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// What each parameter a call changes ranges over: the value of `n` in
+/// `f(n - 1)`, the length of `arr` in `f(arr[1:])`, and for an index such
+/// as `f(i + 1)` the bound it is compared against, `i == len(a)`.
+fn state_parameters(
+    tokens: &[Token],
+    function: &Block,
+    arguments: &[&[Token]],
+    parameters: &[String],
+) -> Vec<SizeSource> {
+    let offset = loops::argument_offset(parameters, arguments);
+    arguments
+        .iter()
+        .enumerate()
+        .filter_map(|(position, arg)| {
+            let parameter = parameters.get(position + offset)?;
+            if !arg.iter().any(|t| t.is_ident(parameter))
+                || matches!(arg, [only] if only.is_ident(parameter))
+            {
+                return None;
+            }
+            // `arr[1:]`, `node.next`, but not `w - wt[i]`
+            if arg.first()?.is_ident(parameter)
+                && arg
+                    .get(1)
+                    .is_some_and(|t| t.is_punct("[") || t.is_punct("."))
+            {
+                return Some(SizeSource::Length(parameter.clone()));
+            }
+            if arg.iter().any(|t| t.is_punct("-")) {
+                return Some(SizeSource::Value(parameter.clone()));
+            }
+            index_bound(tokens, function, parameter)
+        })
+        .collect()
+}
+
+/// The limit an index parameter is checked against in a base case:
+/// `len(a)` for `if i == len(a)`, `n` for `if (i >= n)`.
+fn index_bound(tokens: &[Token], function: &Block, parameter: &str) -> Option<SizeSource> {
+    let body = function.header.end.max(function.span.start)..function.span.end;
+    body.clone().find_map(|i| {
+        if !tokens[i].is_ident(parameter) {
+            return None;
+        }
+        let comparison = tokens.get(i + 1)?;
+        if !matches!(
+            comparison.text.as_str(),
+            "==" | "===" | ">=" | ">" | "<" | "<="
+        ) || comparison.kind != TokenKind::Punctuation
+        {
+            return None;
+        }
+        let start = i + 2;
+        let end = (start..body.end)
+            .find(|&j| {
+                let t = &tokens[j];
+                t.line != comparison.line
+                    || matches!(t.text.as_str(), ":" | ")" | "{" | "&&" | "||" | ";" | "?")
+                    || t.is_keyword("and")
+                    || t.is_keyword("or")
+            })
+            .unwrap_or(body.end);
+        loops::value_source(&tokens[start..end]).filter(|source| source.name() != parameter)
+    })
+}
+
+/// Recursive calls made on a single pass through `block`: of exclusive
+/// branches only the one with the most calls is counted, and calls inside
+/// loops run once per iteration. Branches are exclusive within an
+/// `if`/`else` chain, between an `if` that always returns and the code after
+/// it, and between the two sides of `?:`.
+fn reachable_calls(
+    tokens: &[Token],
+    block: &Block,
+    names: &[&str],
+    enclosing: &Site,
+    symbols: &mut Symbols,
+) -> Vec<Site> {
+    let direct = direct_calls(tokens, block, names, enclosing);
+    let mut calls = sequence_calls(tokens, &block.children, direct, names, enclosing, symbols);
+    calls.sort_unstable_by_key(|site| site.index);
+    calls
+}
+
+/// Calls made by `children`, a run of sibling blocks, and by the `direct`
+/// calls of their parent that lie between and after them.
+fn sequence_calls(
+    tokens: &[Token],
+    children: &[Block],
+    mut direct: Vec<Site>,
+    names: &[&str],
+    enclosing: &Site,
+    symbols: &mut Symbols,
+) -> Vec<Site> {
+    let mut calls: Vec<Site> = Vec::new();
+
+    let mut position = 0;
+    while let Some(child) = children.get(position) {
+        position += 1;
+        if child.is_function() {
+            continue;
+        }
+        if child.kind == BlockKind::Loop {
+            let bound = loops::loop_bound(tokens, child);
+            let iterations = match &bound {
+                LoopBound::Constant(Some(count)) => Fanout::Times(*count),
+                LoopBound::Constant(None) => Fanout::Symbol("k".to_string()),
+                LoopBound::Linear(source) | LoopBound::Logarithmic(source) => {
+                    Fanout::Symbol(match source {
+                        Some(source) => symbols.symbol_for(source),
+                        None => symbols.primary(),
+                    })
+                }
+            };
+            let inner = Site {
+                index: 0,
+                fanout: enclosing.fanout.times(&iterations),
+                loop_variable: loops::loop_index(tokens, child),
+                over_collection: loops::iterates_elements(tokens, child),
+            };
+            calls.extend(reachable_calls(tokens, child, names, &inner, symbols));
+            continue;
+        }
+        if child.kind != BlockKind::Conditional || !starts_with(tokens, child, &["if"]) {
+            calls.extend(reachable_calls(tokens, child, names, enclosing, symbols));
+            continue;
+        }
+
+        let mut chain = vec![child];
+        while let Some(arm) = children
+            .get(position)
+            .filter(|next| starts_with(tokens, next, &["else", "elif"]))
+        {
+            chain.push(arm);
+            position += 1;
+        }
+        let mut arms: Vec<Vec<Site>> = chain
+            .iter()
+            .map(|arm| reachable_calls(tokens, arm, names, enclosing, symbols))
+            .collect();
+
+        // `if (...) { return f(lo, mid); } return f(mid, hi);`: the code
+        // after a chain that always returns is one more arm
+        let has_else = chain.iter().any(|arm| {
+            tokens[arm.span.start].is_keyword("else")
+                && !tokens
+                    .get(arm.span.start + 1)
+                    .is_some_and(|t| t.is_keyword("if"))
+        });
+        if !has_else && chain.iter().all(|arm| always_returns(tokens, arm)) {
+            let end = chain.last().map_or(child.span.end, |arm| arm.span.end);
+            let after = direct.split_off(direct.partition_point(|site| site.index < end));
+            arms.push(sequence_calls(
+                tokens,
+                &children[position..],
+                after,
+                names,
+                enclosing,
+                symbols,
+            ));
+            position = children.len();
+        }
+        if let Some(widest) = arms.into_iter().max_by_key(Vec::len) {
+            calls.extend(widest);
+        }
+    }
+
+    calls.extend(direct);
+    calls
+}
+
+/// Whether `arm` ends by leaving the function: its own statements, outside
+/// nested blocks, include a `return`, `throw` or `raise` after which no
+/// nested block follows.
+fn always_returns(tokens: &[Token], arm: &Block) -> bool {
+    let exit = arm.span.clone().rev().find(|&i| {
+        !arm.header.contains(&i)
+            && !arm.children.iter().any(|child| child.span.contains(&i))
+            && ["return", "throw", "raise"]
+                .iter()
+                .any(|keyword| tokens[i].is_keyword(keyword))
+    });
+    exit.is_some_and(|exit| arm.children.iter().all(|child| child.span.start < exit))
+}
+
+/// Recursive calls in `block` outside its nested blocks, sorted by
+/// position. Of the two sides of a conditional expression `c ? a : b`, only
+/// the one with more calls is kept.
+fn direct_calls(tokens: &[Token], block: &Block, names: &[&str], enclosing: &Site) -> Vec<Site> {
+    let header = if block.is_function() {
+        block.header.clone()
+    } else {
+        0..0
+    };
+    let own = |i: &usize| {
+        !header.contains(i) && !block.children.iter().any(|child| child.span.contains(i))
+    };
+    let mut calls: Vec<usize> = block
+        .span
+        .clone()
+        .filter(|i| {
+            own(i)
+                && names.contains(&tokens[*i].text.as_str())
+                && lexer::is_call(tokens, *i)
+                && !i.checked_sub(1).is_some_and(|p| {
+                    tokens[p].is_punct(".")
+                        && !matches!(
+                            tokens.get(p.wrapping_sub(1)).map(|t| t.text.as_str()),
+                            Some("self" | "this")
+                        )
+                })
+        })
+        .collect();
+
+    // Innermost conditional expressions first, so that an outer one counts
+    // only what remains of the inner ones
+    let questions: Vec<usize> = block
+        .span
+        .clone()
+        .filter(|i| own(i) && tokens[*i].is_punct("?"))
+        .collect();
+    for &question in questions.iter().rev() {
+        let Some(colon) = expression_end(tokens, question + 1, block.span.end, true) else {
+            continue;
+        };
+        let end =
+            expression_end(tokens, colon + 1, block.span.end, false).unwrap_or(block.span.end);
+        let then_calls = calls.iter().filter(|&&i| i > question && i < colon).count();
+        let else_calls = calls.iter().filter(|&&i| i > colon && i < end).count();
+        let dropped = if then_calls >= else_calls {
+            colon..end
+        } else {
+            question..colon
+        };
+        calls.retain(|i| !dropped.contains(i));
+    }
+
+    calls
+        .into_iter()
+        .map(|index| Site {
+            index,
+            fanout: enclosing.fanout.clone(),
+            loop_variable: enclosing.loop_variable.clone(),
+            over_collection: enclosing.over_collection,
+        })
+        .collect()
+}
+
+/// Where a side of a conditional expression starting at `start` ends: at
+/// its `:` when `at_colon`, else at the `;`, `,`, `:` or closing bracket
+/// after it. `None` when the expression ends before reaching a `:` that
+/// `at_colon` asks for, as for Rust's `?` operator.
+fn expression_end(tokens: &[Token], start: usize, limit: usize, at_colon: bool) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut nested = 0usize;
+    for (i, token) in tokens.iter().enumerate().take(limit).skip(start) {
+        if token.kind != TokenKind::Punctuation {
+            continue;
+        }
+        match token.text.as_str() {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" if depth == 0 => return (!at_colon).then_some(i),
+            ")" | "]" | "}" => depth -= 1,
+            "?" if depth == 0 => nested += 1,
+            ":" if depth == 0 && nested > 0 => nested -= 1,
+            ":" if depth == 0 => return Some(i),
+            ";" | "," if depth == 0 => return (!at_colon).then_some(i),
+            _ => {}
+        }
+    }
+    (!at_colon).then_some(limit)
+}
+
+fn starts_with(tokens: &[Token], block: &Block, keywords: &[&str]) -> bool {
+    tokens
+        .get(block.span.start)
+        .is_some_and(|t| t.kind == TokenKind::Keyword && keywords.contains(&t.text.as_str()))
+}
+
+/// Locals that every recursive call is split around, like the pivot
+/// index in `sort(lo, p - 1)` and `sort(p + 1, hi)`.
+fn split_variables(
+    tokens: &[Token],
+    own: &[usize],
+    calls: &[usize],
+    parameters: &[String],
+) -> Vec<String> {
+    if calls.len() < 2 {
+        return Vec::new();
+    }
+    let locals: Vec<&str> = own
+        .iter()
+        .filter(|&&i| {
+            tokens[i].kind == TokenKind::Identifier
+                && tokens.get(i + 1).is_some_and(|t| t.is_punct("="))
+                && !parameters.contains(&tokens[i].text)
+        })
+        .map(|&i| tokens[i].text.as_str())
+        .collect();
+    locals
+        .into_iter()
+        .filter(|local| {
+            calls.iter().all(|&call| {
+                loops::call_arguments(tokens, call + 1)
+                    .iter()
+                    .any(|arg| arg.iter().any(|t| t.is_ident(local)))
+            })
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+/// How the arguments of one call shrink its input; halving wins over
+/// subtracting.
+fn call_shrink(arguments: &[&[Token]], split: &[String], parameters: &[String]) -> Option<Shrink> {
+    let shrinks: Vec<Shrink> = arguments
+        .iter()
+        .filter_map(|arg| argument_shrink(arg, split, parameters))
+        .collect();
+    shrinks
+        .iter()
+        .copied()
+        .filter(|s| matches!(s, Shrink::Fraction { .. }))
+        .min_by(|a, b| a.ratio().total_cmp(&b.ratio()))
+        .or_else(|| {
+            shrinks
+                .iter()
+                .copied()
+                .filter_map(|s| match s {
+                    Shrink::Subtract(c) => Some(c),
+                    Shrink::Fraction { .. } | Shrink::Element => None,
+                })
+                .max()
+                .map(Shrink::Subtract)
+        })
+}
+
+/// How one argument shrinks the input. `split` holds the locals that halve
+/// it, such as a midpoint or a pivot.
+fn argument_shrink(arg: &[Token], split: &[String], parameters: &[String]) -> Option<Shrink> {
+    let number = |t: &Token| {
+        (t.kind == TokenKind::Number)
+            .then(|| t.text.parse::<u32>().ok())
+            .flatten()
+    };
+    let half = Shrink::Fraction {
+        numerator: 1,
+        denominator: 2,
+    };
+
+    // `n - 1`, `i + 1`: a parameter stepped by a constant
+    if let [first, operator, step] = arg
+        && parameters.contains(&first.text)
+        && (operator.is_punct("-") || operator.is_punct("+"))
+        && let Some(c) = number(step).filter(|&c| c > 0)
+    {
+        return Some(Shrink::Subtract(c));
+    }
+
+    // `mid`, a split point, or one half of a tree
+    if arg.iter().any(|t| {
+        t.kind == TokenKind::Identifier
+            && (t.text.eq_ignore_ascii_case("mid")
+                || split.contains(&t.text)
+                || t.text == "left"
+                || t.text == "right")
+    }) {
+        return Some(half);
+    }
+
+    for (i, token) in arg.iter().enumerate() {
+        let next = arg.get(i + 1).and_then(number);
+        match token.text.as_str() {
+            // `n / 3`, `2 * n / 3`, `n >> 1`
+            "/" | "//" if token.kind == TokenKind::Punctuation => {
+                if let Some(denominator) = next.filter(|&d| d >= 2) {
+                    let numerator = arg[..i]
+                        .windows(2)
+                        .find_map(|w| match (number(&w[0]), w[1].is_punct("*")) {
+                            (Some(k), true) => Some(k),
+                            _ => None,
+                        })
+                        .or_else(|| {
+                            arg[..i]
+                                .windows(2)
+                                .find_map(|w| w[0].is_punct("*").then(|| number(&w[1])).flatten())
+                        })
+                        .unwrap_or(1)
+                        .min(denominator - 1);
+                    return Some(Shrink::Fraction {
+                        numerator,
+                        denominator,
+                    });
+                }
+            }
+            ">>" if token.kind == TokenKind::Punctuation => {
+                if let Some(shift) = next.filter(|&s| (1..16).contains(&s)) {
+                    return Some(Shrink::Fraction {
+                        numerator: 1,
+                        denominator: 1 << shift,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for (i, token) in arg.iter().enumerate() {
+        let next = arg.get(i + 1).and_then(number);
+        match token.text.as_str() {
+            // `n - 1`, `i + 1`, `amount - coin`
+            "-" | "+" if token.kind == TokenKind::Punctuation && i > 0 => {
+                if let Some(c) = next.filter(|&c| c > 0) {
+                    return Some(Shrink::Subtract(c));
+                }
+                if token.text == "-"
+                    && arg
+                        .get(i + 1)
+                        .is_some_and(|t| t.kind == TokenKind::Identifier)
+                {
+                    return Some(Shrink::Subtract(1));
+                }
+            }
+            // `arr[1:]`, `&arr[1..]`
+            "[" => {
+                if let (Some(c), Some(colon)) = (next, arg.get(i + 2))
+                    && c > 0
+                    && (colon.is_punct(":") || colon.is_punct(".."))
+                {
+                    return Some(Shrink::Subtract(c));
+                }
+            }
+            // `node.next`, `list.tail`
+            "next" | "tail" | "rest" if i > 0 && arg[i - 1].is_punct(".") => {
+                return Some(Shrink::Subtract(1));
+            }
+            // `s.slice(1)`, `s.substring(1)`
+            "slice" | "substring" | "subList" if i > 0 && arg[i - 1].is_punct(".") => {
+                if let Some(c) = arg.get(i + 2).and_then(number).filter(|&c| c > 0) {
+                    return Some(Shrink::Subtract(c));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Whether the function copies parts of its input: Python slices,
+/// `slice()`, `substring()`, `Arrays.copyOfRange()`.
+fn copies_input(tokens: &[Token], own: &[usize], language: &str) -> bool {
+    own.iter().any(|&i| {
+        let token = &tokens[i];
+        if lexer::is_call(tokens, i) && COPYING_CALLS.contains(&token.text.as_str()) {
+            return true;
+        }
+        // `arr[:mid]`, `arr[1:]`
+        language == "python"
+            && token.is_punct("[")
+            && lexer::matching_close(tokens, i)
+                .is_some_and(|close| tokens[i + 1..close].iter().any(|t| t.is_punct(":")))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks;
+
+    /// The recurrence of the first function in `code`.
+    fn recurrence(code: &str, language: &str) -> Recurrence {
+        let tokens: Vec<Token> = lexer::tokenize(code, language)
+            .into_iter()
+            .filter(|t| !t.is_trivia())
+            .collect();
+        let tree = blocks::build_block_tree(&tokens, language);
+        let function = tree.functions()[0];
+        let BlockKind::Function(name) = &function.kind else {
+            unreachable!()
+        };
+        let (recurrence, _) = extract(
+            &tokens,
+            function,
+            &[name.as_str()],
+            &[],
+            &mut Symbols::default(),
+            &Complexity::constant(),
+            language,
+        )
+        .expect("a recursive call");
+        recurrence
+    }
+
+    #[test]
+    fn call_after_returning_if_is_exclusive() {
+        let c = r#"
+            int bs(int *a, int lo, int hi, int t) {
+                if (lo >= hi) return -1;
+                int mid = (lo + hi) / 2;
+                if (a[mid] < t) {
+                    return bs(a, mid + 1, hi, t);
+                }
+                return bs(a, lo, mid, t);
+            }
+        "#;
+        let found = recurrence(c, "c");
+        assert_eq!(found.calls.len(), 1);
+        assert_eq!(found.solve().complexity, Complexity::logarithmic());
+
+        let python = r#"
+def bs(a, lo, hi, t):
+    if lo >= hi:
+        return -1
+    mid = (lo + hi) // 2
+    if a[mid] < t:
+        return bs(a, mid + 1, hi, t)
+    return bs(a, lo, mid, t)
+"#;
+        let found = recurrence(python, "python");
+        assert_eq!(found.calls.len(), 1);
+        assert_eq!(found.solve().complexity, Complexity::logarithmic());
+    }
+
+    #[test]
+    fn sides_of_conditional_expression_are_exclusive() {
+        let js = r#"
+            function bs(a, lo, hi, t) {
+                if (lo >= hi) return -1;
+                const mid = Math.floor((lo + hi) / 2);
+                return a[mid] < t ? bs(a, mid + 1, hi, t) : bs(a, lo, mid, t);
+            }
+        "#;
+        let found = recurrence(js, "javascript");
+        assert_eq!(found.calls.len(), 1);
+        assert_eq!(found.solve().complexity, Complexity::logarithmic());
+    }
+
+    #[test]
+    fn calls_after_non_returning_if_still_add_up() {
+        let c = r#"
+            void sort(int *a, int lo, int hi) {
+                if (hi - lo < 2) {
+                    count++;
+                }
+                int mid = (lo + hi) / 2;
+                sort(a, lo, mid);
+                sort(a, mid, hi);
+            }
+        "#;
+        let found = recurrence(c, "c");
+        assert_eq!(found.calls.len(), 2);
+        assert_eq!(found.solve().complexity, Complexity::linear());
+    }
+
+    #[test]
+    fn both_calls_of_one_side_count() {
+        let js = r#"
+            function fib(n) {
+                return n < 2 ? n : fib(n - 1) + fib(n - 2);
+            }
+        "#;
+        let found = recurrence(js, "javascript");
+        assert_eq!(found.calls.len(), 2);
+        assert!(found.solve().complexity.is_exponential());
+    }
+
+    #[test]
+    fn only_unmeasured_helper_calls_take_linear_time() {
+        let locals = r#"
+def total(arr, lo, hi):
+    if lo >= hi:
+        return 0
+    mid = (lo + hi) // 2
+    merged = total(arr, lo, mid) + total(arr, mid + 1, hi)
+    return merged
+"#;
+        let found = recurrence(locals, "python");
+        assert_eq!(found.solve().complexity, Complexity::linear());
+
+        let helper = r#"
+def msort(lo, hi):
+    if hi - lo < 2:
+        return
+    mid = (lo + hi) // 2
+    msort(lo, mid)
+    msort(mid, hi)
+    merge(lo, mid, hi)
+"#;
+        let found = recurrence(helper, "python");
+        assert_eq!(found.solve().complexity, Complexity::linearithmic());
+    }
+
+    #[test]
+    fn only_midpoints_halve() {
+        let stepped = r#"
+def count_down(n, pyramid):
+    if n == 0:
+        return
+    count_down(n - 1, pyramid)
+"#;
+        let found = recurrence(stepped, "python");
+        assert_eq!(found.solve().complexity, Complexity::linear());
+
+        let middle = r#"
+def find(a, lo, hi, t):
+    if lo >= hi:
+        return lo
+    middle = lo + (hi - lo) // 2
+    if a[middle] < t:
+        return find(a, middle + 1, hi, t)
+    return find(a, lo, middle, t)
+"#;
+        let found = recurrence(middle, "python");
+        assert_eq!(found.solve().complexity, Complexity::logarithmic());
+    }
+}
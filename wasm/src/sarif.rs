@@ -0,0 +1,202 @@
+//! SARIF 2.1 output.
+//!
+//! Reports the functions over their budget as SARIF results, the
+//! format code-scanning dashboards read. Each result names the rule for the
+//! kind of finding and points at the lines of the function; its message is
+//! the analysis details.
+
+use crate::budget::Budget;
+use crate::complexity::Complexity;
+use crate::{AnalysisResult, FunctionAnalysis};
+use serde::Serialize;
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// The kinds of finding, in the order their rules are listed.
+const RULES: &[(&str, &str)] = &[
+    (
+        "exponential-recursion",
+        "Exponential or factorial time, usually from recursion branching into several calls",
+    ),
+    (
+        "quadratic-in-loop",
+        "Nested loops multiplying to quadratic or worse time",
+    ),
+    (
+        "expensive-call",
+        "A call to a function that is itself over budget, or repeated by the loops around it",
+    ),
+    ("complexity-threshold", "Time complexity over the budget"),
+];
+
+/// A SARIF log with a single run of the analyzer.
+#[derive(Debug, Serialize)]
+pub struct SarifLog {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<Run>,
+}
+
+#[derive(Debug, Serialize)]
+struct Run {
+    tool: Tool,
+    results: Vec<SarifResult>,
+}
+
+#[derive(Debug, Serialize)]
+struct Tool {
+    driver: Driver,
+}
+
+#[derive(Debug, Serialize)]
+struct Driver {
+    name: &'static str,
+    version: &'static str,
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule {
+    id: &'static str,
+    short_description: Message,
+    default_configuration: Configuration,
+}
+
+#[derive(Debug, Serialize)]
+struct Configuration {
+    level: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct Message {
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: &'static str,
+    rule_index: usize,
+    level: &'static str,
+    message: Message,
+    locations: Vec<Location>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    physical_location: PhysicalLocation,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    region: Region,
+}
+
+#[derive(Debug, Serialize)]
+struct ArtifactLocation {
+    uri: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: usize,
+    end_line: usize,
+}
+
+/// A SARIF log of every function of `files` over `budget`. Each file is
+/// given by its path, which becomes the artifact URI.
+pub fn to_sarif(files: &[(&str, &AnalysisResult)], budget: &Budget) -> SarifLog {
+    let results = files
+        .iter()
+        .flat_map(|&(path, result)| {
+            result.functions().iter().filter_map(move |function| {
+                let (max, _) = budget.limit(path, function.function());
+                let over = function.complexity().order(max).is_gt();
+                over.then(|| finding(path, function, max))
+            })
+        })
+        .collect();
+
+    let rules = RULES
+        .iter()
+        .map(|&(id, description)| Rule {
+            id,
+            short_description: Message {
+                text: description.to_string(),
+            },
+            default_configuration: Configuration { level: "warning" },
+        })
+        .collect();
+
+    SarifLog {
+        schema: SCHEMA,
+        version: "2.1.0",
+        runs: vec![Run {
+            tool: Tool {
+                driver: Driver {
+                    name: env!("CARGO_PKG_NAME"),
+                    version: env!("CARGO_PKG_VERSION"),
+                    rules,
+                },
+            },
+            results,
+        }],
+    }
+}
+
+fn finding(path: &str, function: &FunctionAnalysis, budget: &Complexity) -> SarifResult {
+    let rule_id = rule(function);
+    let mut text = format!(
+        "'{}' is {}, over its budget of {}.",
+        function.function(),
+        function.complexity(),
+        budget
+    );
+    for detail in function.details() {
+        text.push_str(&format!("\n{}", detail));
+    }
+
+    SarifResult {
+        rule_id,
+        rule_index: RULES.iter().position(|&(id, _)| id == rule_id).unwrap_or(0),
+        level: "warning",
+        message: Message { text },
+        locations: vec![Location {
+            physical_location: PhysicalLocation {
+                artifact_location: ArtifactLocation {
+                    uri: path.replace('\\', "/"),
+                },
+                region: Region {
+                    start_line: function.line_start(),
+                    end_line: function.line_end(),
+                },
+            },
+        }],
+    }
+}
+
+/// The rule a function over budget breaks: exponential growth first,
+/// then loops accounting for a polynomial of degree two or more, then calls
+/// into other functions.
+fn rule(function: &FunctionAnalysis) -> &'static str {
+    let complexity = function.complexity();
+    if complexity.is_exponential() {
+        "exponential-recursion"
+    } else if complexity.degree() >= 2 && function.cost_tree().cost() >= complexity {
+        "quadratic-in-loop"
+    } else if function
+        .details()
+        .iter()
+        .any(|detail| detail.starts_with("Call to '"))
+    {
+        "expensive-call"
+    } else {
+        "complexity-threshold"
+    }
+}
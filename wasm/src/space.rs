@@ -0,0 +1,654 @@
+//! Auxiliary space.
+//!
+//! Adds up the memory a function needs beyond its input: arrays allocated
+//! with a size, copies of the input (slices, `list(xs)`, `.collect()`),
+//! collections grown inside loops, the recursion stack and memo or DP
+//! tables.
+
+use crate::blocks::Block;
+use crate::complexity::Complexity;
+use crate::cost;
+use crate::lexer::{self, Token, TokenKind};
+use crate::loops::{self, LoopBound, SizeSource, Symbols};
+use crate::tables::{self, Table};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Calls that build a new collection from their argument.
+const COPYING_CONSTRUCTORS: &[&str] = &[
+    "list",
+    "sorted",
+    "set",
+    "dict",
+    "tuple",
+    "deque",
+    "Counter",
+    "bytearray",
+    "copyOf",
+    "copyOfRange",
+    "ArrayList",
+    "LinkedList",
+    "HashSet",
+    "HashMap",
+    "TreeSet",
+    "TreeMap",
+    "ArrayDeque",
+];
+
+/// Methods returning a copy of their receiver.
+const COPYING_METHODS: &[&str] = &[
+    "copy",
+    "clone",
+    "to_vec",
+    "to_owned",
+    "slice",
+    "concat",
+    "split",
+    "collect",
+    "toArray",
+    "toList",
+    "substring",
+    "substr",
+];
+
+/// Array methods that build a new array in JavaScript.
+const JS_COPYING_METHODS: &[&str] = &["map", "filter", "flat", "flatMap"];
+
+/// Methods adding elements to a collection.
+const GROWING_METHODS: &[&str] = &[
+    "append",
+    "appendleft",
+    "extend",
+    "push",
+    "push_back",
+    "push_front",
+    "emplace_back",
+    "unshift",
+    "add",
+    "addLast",
+    "addFirst",
+    "offer",
+    "insert",
+    "put",
+    "set",
+    "setdefault",
+    "push_str",
+];
+
+/// Constructors of empty maps, which grow by assigning to new keys.
+const MAP_CONSTRUCTORS: &[&str] = &["dict", "defaultdict", "Counter", "OrderedDict"];
+
+/// Auxiliary space of a function.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpaceAnalysis {
+    complexity: Complexity,
+    confidence: f64,
+    details: Vec<String>,
+}
+
+impl SpaceAnalysis {
+    pub fn complexity(&self) -> &Complexity {
+        &self.complexity
+    }
+
+    /// How reliable `complexity` is, from 0 to 1.
+    pub fn confidence(&self) -> f64 {
+        self.confidence
+    }
+
+    /// What the memory is spent on.
+    pub fn details(&self) -> &[String] {
+        &self.details
+    }
+}
+
+/// What a recursive function keeps alive while it runs.
+pub struct Recursion {
+    /// Deepest chain of nested calls.
+    pub depth: Complexity,
+    /// Whether every call works on a fraction of the input, so that
+    /// per-call allocations shrink geometrically down the stack.
+    pub divides: bool,
+    /// Entries of the memo table, when results are cached.
+    pub memo: Option<Complexity>,
+}
+
+/// Space needed by `function` on top of its input.
+pub fn space(
+    tokens: &[Token],
+    function: &Block,
+    symbols: &mut Symbols,
+    language: &str,
+    recursion: Option<&Recursion>,
+    tables: &[Table],
+) -> SpaceAnalysis {
+    let mut details = Vec::new();
+    let mut confidence = 0.9f64;
+    let signature = &tokens[function.span.start..function.header.end.max(function.span.start)];
+    let parameters = loops::parameters(signature);
+    let body: Vec<usize> = function
+        .own_token_indices()
+        .into_iter()
+        .filter(|i| !function.header.contains(i))
+        .collect();
+
+    // Elements held by each local collection, as far as it is known
+    let mut sizes: HashMap<String, Complexity> = HashMap::new();
+    let mut frame = Complexity::constant();
+
+    for allocation in tables::allocations(tokens, function) {
+        if tables.iter().any(|table| table.name == allocation.name) {
+            continue;
+        }
+        let size = allocation.size(symbols);
+        if size.is_constant() {
+            continue;
+        }
+        details.push(format!(
+            "'{}' allocates {} at line {}",
+            allocation.name, size, allocation.line
+        ));
+        frame = frame.plus(&size);
+        sizes.insert(allocation.name, size);
+    }
+
+    // Arrays built straight into the result: `return new int[n][m]`, or a
+    // Rust tail expression such as `vec![0; n]`
+    for (line, value) in returned_values(tokens, function, &body, language) {
+        let Some(size) = tables::allocation_size(value, symbols) else {
+            continue;
+        };
+        if size.is_constant() {
+            continue;
+        }
+        details.push(format!("Return value at line {} allocates {}", line, size));
+        frame = frame.plus(&size);
+    }
+
+    // Latest assignment to each name, so a collection reset inside a loop
+    // only grows with the loops nested inside that one
+    let mut assigned: HashMap<&str, usize> = HashMap::new();
+    let mut grown: Vec<(String, usize, Complexity)> = Vec::new();
+    for &i in &body {
+        let token = &tokens[i];
+
+        if token.is_punct("=") || token.is_punct(":=") {
+            let Some((name, subscripts, _)) = tables::assignment_target(tokens, body[0], i) else {
+                continue;
+            };
+            if subscripts.is_empty() {
+                assigned.insert(name, i);
+                continue;
+            }
+            // `seen[key] = True` on a map created empty
+            let creates_map = assigned.get(name).is_some_and(|&at| {
+                let value = &tokens[at + 1..];
+                matches!(value, [open, close, ..] if open.is_punct("{") && close.is_punct("}"))
+                    || value.first().is_some_and(|first| {
+                        MAP_CONSTRUCTORS.contains(&first.text.as_str())
+                            || (first.is_keyword("new") || first.is_ident("new"))
+                                && value
+                                    .get(1)
+                                    .is_some_and(|t| t.is_ident("Map") || t.text.ends_with("Map"))
+                    })
+            });
+            if creates_map {
+                let growth = growth(tokens, function, i, assigned.get(name).copied(), symbols);
+                if !growth.is_constant() {
+                    grow(&mut sizes, name, &growth);
+                    grown.push((name.to_string(), token.line, growth));
+                }
+            }
+            continue;
+        }
+
+        if token.is_punct(".")
+            && tokens.get(i + 1).is_some_and(|method| {
+                GROWING_METHODS.contains(&method.text.as_str()) && lexer::is_call(tokens, i + 1)
+            })
+        {
+            let Some(root) = chain_root(tokens, i) else {
+                continue;
+            };
+            let name = tokens[root].text.as_str();
+            let mut growth = growth(tokens, function, i, assigned.get(name).copied(), symbols);
+            if growth.is_constant() {
+                continue;
+            }
+            // Appending a collection of known size: `rows.append(row)`,
+            // `grid.push(new Array(n).fill(0))`
+            let element = match loops::call_arguments(tokens, i + 2)[..] {
+                [[name]] => sizes.get(&name.text).cloned(),
+                [argument] => tables::allocation_size(argument, symbols),
+                _ => None,
+            };
+            if let Some(size) = element {
+                growth = growth.times(&size);
+            }
+            grow(&mut sizes, name, &growth);
+            grown.push((name.to_string(), token.line, growth));
+            continue;
+        }
+
+        if let Some((name, size)) = copy(tokens, i, language, &parameters, &sizes, symbols) {
+            details.push(format!(
+                "Copy of '{}' at line {} takes {}",
+                name, token.line, size
+            ));
+            frame = frame.plus(&size);
+        }
+    }
+
+    // Report each collection once, at its largest
+    let mut reported: Vec<&str> = Vec::new();
+    for (name, line, growth) in &grown {
+        if reported.contains(&name.as_str()) {
+            continue;
+        }
+        let largest = grown
+            .iter()
+            .filter(|(other, _, _)| other == name)
+            .map(|(_, _, size)| size)
+            .max()
+            .unwrap_or(growth);
+        details.push(format!(
+            "'{}' grows inside loops from line {}: {}",
+            name, line, largest
+        ));
+        frame = frame.plus(largest);
+        reported.push(name);
+    }
+
+    let mut complexity = frame.clone();
+    if let Some(recursion) = recursion {
+        confidence = 0.8f64;
+        details.push(format!("Recursion stack depth {}", recursion.depth));
+        let stack = if frame.is_constant() {
+            recursion.depth.clone()
+        } else if recursion.divides {
+            details.push(format!(
+                "Allocations halve at each level of the recursion, so the stack holds {} in total",
+                frame
+            ));
+            recursion.depth.plus(&frame)
+        } else {
+            let stack = recursion.depth.times(&frame);
+            details.push(format!(
+                "Each of the {} stack frames holds {}: {}",
+                recursion.depth, frame, stack
+            ));
+            stack
+        };
+        complexity = complexity.plus(&stack);
+        if let Some(memo) = &recursion.memo {
+            details.push(format!("Memo table holds {} entries", memo));
+            complexity = complexity.plus(memo);
+        }
+    }
+
+    for table in tables {
+        details.push(format!("DP table '{}' holds {}", table.name, table.size()));
+        complexity = complexity.plus(&table.size());
+    }
+
+    if complexity.is_constant() {
+        details.push("No allocations proportional to the input".to_string());
+    }
+
+    SpaceAnalysis {
+        complexity,
+        confidence,
+        details,
+    }
+}
+
+/// Collections built by `function` outside its nested functions, at the
+/// token index where each is built, with the time it takes: arrays
+/// allocated with a size, copies of the input or of a sized local, and
+/// Python comprehensions, which loop once per `for` clause.
+pub fn construction(
+    tokens: &[Token],
+    function: &Block,
+    symbols: &mut Symbols,
+    language: &str,
+) -> Vec<(usize, Complexity)> {
+    let signature = &tokens[function.span.start..function.header.end.max(function.span.start)];
+    let parameters = loops::parameters(signature);
+    let sizes: HashMap<String, Complexity> = tables::allocations(tokens, function)
+        .into_iter()
+        .map(|allocation| {
+            let size = allocation.size(symbols);
+            (allocation.name, size)
+        })
+        .collect();
+
+    let mut built: Vec<(usize, Complexity)> = Vec::new();
+    for i in function.own_token_indices() {
+        if function.header.contains(&i) {
+            continue;
+        }
+        let token = &tokens[i];
+        let mut time = Complexity::constant();
+
+        // `xs = [0] * n`, `return new int[n]`, `vec![0; n]`
+        let value = if token.is_punct("=") || token.is_punct(":=") || token.is_keyword("return") {
+            Some(i + 1)
+        } else if token.is_keyword("new")
+            || token.is_ident("new")
+            || token.is_ident("vec") && tokens.get(i + 1).is_some_and(|t| t.is_punct("!"))
+        {
+            Some(i)
+        } else {
+            None
+        };
+        if let Some(start) = value {
+            let end = tables::statement_end(tokens, start, function.span.end);
+            if let Some(size) = tables::allocation_size(&tokens[start..end], symbols) {
+                time = time.plus(&size);
+            }
+        }
+
+        if let Some((_, size)) = copy(tokens, i, language, &parameters, &sizes, symbols) {
+            time = time.plus(&size);
+        }
+
+        // `[(a, b) for a in xs for b in xs]`
+        let literal = i.checked_sub(1).is_none_or(|p| !is_subscripted(&tokens[p]));
+        if language == "python"
+            && (token.is_punct("[") || token.is_punct("{"))
+            && literal
+            && let Some(close) = lexer::matching_close(tokens, i)
+            && let Some(loops) = comprehension_time(&tokens[i + 1..close], symbols)
+        {
+            time = time.plus(&loops);
+        }
+
+        if time.is_constant() {
+            continue;
+        }
+        // One entry per line: `int[] a = new int[n]` is seen at `=` and `new`
+        match built.last_mut() {
+            Some((last, total)) if tokens[*last].line == token.line => {
+                *total = total.plus(&time);
+            }
+            _ => built.push((i, time)),
+        }
+    }
+    built
+}
+
+/// Whether a `[` after `token` subscripts it rather than opening a list.
+fn is_subscripted(token: &Token) -> bool {
+    token.kind == TokenKind::Identifier || token.is_punct(")") || token.is_punct("]")
+}
+
+/// Time a comprehension with the contents `inner` takes: the iterations of
+/// each `for` clause times the cost of building one element, or `None` if
+/// `inner` is not a comprehension.
+fn comprehension_time(inner: &[Token], symbols: &mut Symbols) -> Option<Complexity> {
+    // `for`, `in` and `if` outside any nested brackets
+    let mut depth = 0usize;
+    let mut clauses: Vec<usize> = Vec::new();
+    for (i, token) in inner.iter().enumerate() {
+        if token.is_punct("(") || token.is_punct("[") || token.is_punct("{") {
+            depth += 1;
+        } else if token.is_punct(")") || token.is_punct("]") || token.is_punct("}") {
+            depth = depth.saturating_sub(1);
+        } else if depth == 0
+            && (token.is_keyword("for") || token.is_keyword("in") || token.is_keyword("if"))
+        {
+            clauses.push(i);
+        }
+    }
+    let first = *clauses.iter().find(|&&i| inner[i].is_keyword("for"))?;
+
+    // `[[0] * m for _ in range(n)]`, `[[x for x in row] for row in grid]`
+    let element = &inner[..first];
+    let nested = element.first().is_some_and(|t| t.is_punct("["))
+        && lexer::matching_close(element, 0) == Some(element.len() - 1);
+    let mut time = nested
+        .then(|| comprehension_time(&element[1..element.len() - 1], symbols))
+        .flatten()
+        .or_else(|| tables::allocation_size(element, symbols))
+        .unwrap_or_else(Complexity::constant);
+
+    for (position, &clause) in clauses.iter().enumerate() {
+        if !inner[clause].is_keyword("for") {
+            continue;
+        }
+        let Some(&within) = clauses[position + 1..]
+            .iter()
+            .find(|&&i| inner[i].is_keyword("in"))
+        else {
+            continue;
+        };
+        let end = clauses
+            .iter()
+            .copied()
+            .find(|&i| i > within && !inner[i].is_keyword("in"))
+            .unwrap_or(inner.len());
+        let iterations = match symbols
+            .fold_constants(loops::iterable_bound(&inner[within + 1..end]))
+        {
+            LoopBound::Constant(_) => Complexity::constant(),
+            LoopBound::Linear(Some(source)) => Complexity::variable(&symbols.symbol_for(&source)),
+            LoopBound::Linear(None) => Complexity::variable(&symbols.primary()),
+            LoopBound::Logarithmic(Some(source)) => {
+                Complexity::log_of(&symbols.symbol_for(&source))
+            }
+            LoopBound::Logarithmic(None) => Complexity::log_of(&symbols.primary()),
+        };
+        time = time.times(&iterations);
+    }
+    Some(time)
+}
+
+/// The collection a list comprehension iterates over, when it is a single
+/// name: `xs` for `x * 2 for x in xs if x`.
+fn comprehension_source(inner: &[Token]) -> Option<&Token> {
+    let comprehension = inner.iter().position(|t| t.is_keyword("for"))?;
+    let within = inner[comprehension..]
+        .iter()
+        .position(|t| t.is_keyword("in"))?;
+    match &inner[comprehension + within + 1..] {
+        [source, rest @ ..] if source.kind == TokenKind::Identifier => rest
+            .first()
+            .is_none_or(|next| next.is_keyword("if"))
+            .then_some(source),
+        _ => None,
+    }
+}
+
+/// Each `return` value of `function` with its line, and for Rust the tail
+/// expression ending the body.
+fn returned_values<'a>(
+    tokens: &'a [Token],
+    function: &Block,
+    body: &[usize],
+    language: &str,
+) -> Vec<(usize, &'a [Token])> {
+    let mut values: Vec<(usize, &[Token])> = body
+        .iter()
+        .filter(|&&i| tokens[i].is_keyword("return"))
+        .map(|&i| {
+            let end = tables::statement_end(tokens, i + 1, function.span.end);
+            (tokens[i].line, &tokens[i + 1..end])
+        })
+        .collect();
+
+    if language == "rust"
+        && let [.., last, close] = body[..]
+        && tokens[close].is_punct("}")
+    {
+        // Back to the `;` or brace before the tail, skipping brackets
+        let mut depth = 0usize;
+        let start = body[..body.len() - 1]
+            .iter()
+            .rev()
+            .find(|&&i| {
+                let token = &tokens[i];
+                if token.is_punct(")") || token.is_punct("]") {
+                    depth += 1;
+                } else if token.is_punct("(") || token.is_punct("[") {
+                    depth = depth.saturating_sub(1);
+                }
+                depth == 0 && (token.is_punct(";") || token.is_punct("{") || token.is_punct("}"))
+            })
+            .map_or(last, |&i| i + 1);
+        if start <= last {
+            values.push((tokens[start].line, &tokens[start..=last]));
+        }
+    }
+    values
+}
+
+/// Records that the collection `name` holds at least `size` elements.
+fn grow(sizes: &mut HashMap<String, Complexity>, name: &str, size: &Complexity) {
+    let entry = sizes
+        .entry(name.to_string())
+        .or_insert_with(Complexity::constant);
+    if *size > *entry {
+        *entry = size.clone();
+    }
+}
+
+/// Elements added by the operation at `index`: one per iteration of the
+/// loops around it, leaving out loops that also contain the collection's
+/// latest assignment at `assigned`.
+fn growth(
+    tokens: &[Token],
+    function: &Block,
+    index: usize,
+    assigned: Option<usize>,
+    symbols: &mut Symbols,
+) -> Complexity {
+    let chain: Vec<&Block> = function
+        .enclosing_loops(index)
+        .into_iter()
+        .filter(|block| assigned.is_none_or(|at| !block.span.contains(&at)))
+        .collect();
+    cost::loop_iterations(tokens, &chain, symbols)
+        .iter()
+        .fold(Complexity::constant(), |total, iterations| {
+            total.times(iterations)
+        })
+}
+
+/// First identifier of the receiver chain ending at the `.` at `dot`:
+/// `xs` for `xs.iter().map(f).collect()`, `items` for `self.items.push(x)`.
+fn chain_root(tokens: &[Token], dot: usize) -> Option<usize> {
+    let mut i = dot.checked_sub(1)?;
+    loop {
+        if tokens[i].is_punct(")") || tokens[i].is_punct("]") {
+            i = opening(tokens, i)?.checked_sub(1)?;
+            continue;
+        }
+        if tokens[i].kind != TokenKind::Identifier {
+            return None;
+        }
+        match i.checked_sub(1) {
+            Some(previous) if tokens[previous].is_punct(".") => i = previous.checked_sub(1)?,
+            _ => break,
+        }
+    }
+    // `self.items` and `this.items` name the field
+    if tokens[i].text == "self" || tokens[i].text == "this" {
+        return Some(i + 2).filter(|&field| field < dot);
+    }
+    Some(i)
+}
+
+/// Index of the bracket opening the one closed at `close`.
+fn opening(tokens: &[Token], close: usize) -> Option<usize> {
+    let open = match tokens[close].text.as_str() {
+        ")" => "(",
+        "]" => "[",
+        _ => return None,
+    };
+    let mut depth = 0usize;
+    for i in (0..=close).rev() {
+        if tokens[i].is_punct(&tokens[close].text) {
+            depth += 1;
+        } else if tokens[i].is_punct(open) {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+/// A copy of a parameter or a sized local made at `index`: `xs[1:]`,
+/// `list(xs)`, `xs.slice()`, `[...xs]`, `new ArrayList<>(xs)`.
+fn copy(
+    tokens: &[Token],
+    index: usize,
+    language: &str,
+    parameters: &[String],
+    sizes: &HashMap<String, Complexity>,
+    symbols: &mut Symbols,
+) -> Option<(String, Complexity)> {
+    let token = &tokens[index];
+    let copied = if token.is_punct("[") && index > 0 {
+        // `xs[1:]`
+        let receiver = &tokens[index - 1];
+        let close = lexer::matching_close(tokens, index)?;
+        let sliced = language == "python"
+            && receiver.kind == TokenKind::Identifier
+            && loops::split_top_level(&tokens[index + 1..close], ":").len() > 1;
+        // `[...xs]`
+        let spread = tokens.get(index + 1).is_some_and(|t| t.is_punct("..."));
+        // `[x * 2 for x in xs]`
+        let comprehension = (language == "python" && !is_subscripted(receiver))
+            .then(|| comprehension_source(&tokens[index + 1..close]))
+            .flatten();
+        if sliced {
+            receiver
+        } else if spread {
+            tokens.get(index + 2)?
+        } else {
+            comprehension?
+        }
+    } else if token.kind == TokenKind::Identifier
+        && COPYING_CONSTRUCTORS.contains(&token.text.as_str())
+    {
+        // `list(xs)`, `Arrays.copyOf(xs, n)`, `new ArrayList<>(xs)`, but
+        // not `memo.set(n, value)`
+        let method = index > 0 && tokens[index - 1].is_punct(".");
+        if method && !token.text.starts_with("copyOf") {
+            return None;
+        }
+        let open = (index + 1..tokens.len().min(index + 6)).find(|&i| tokens[i].is_punct("("))?;
+        if !tokens[index + 1..open].iter().all(|t| {
+            t.kind == TokenKind::Identifier || t.text.chars().all(|c| c == '<' || c == '>')
+        }) {
+            return None;
+        }
+        match loops::call_arguments(tokens, open).first() {
+            Some([argument]) => argument,
+            _ => return None,
+        }
+    } else if token.is_punct(".") {
+        let method = tokens.get(index + 1)?;
+        let copies = COPYING_METHODS.contains(&method.text.as_str())
+            || (matches!(language, "javascript" | "typescript")
+                && JS_COPYING_METHODS.contains(&method.text.as_str()));
+        if !copies || !lexer::is_call(tokens, index + 1) {
+            return None;
+        }
+        &tokens[chain_root(tokens, index)?]
+    } else {
+        return None;
+    };
+
+    let name = copied.text.as_str();
+    let size = match sizes.get(name) {
+        Some(size) => size.clone(),
+        None if parameters.iter().any(|p| p == name) => {
+            Complexity::variable(&symbols.symbol_for(&SizeSource::Length(name.to_string())))
+        }
+        None => return None,
+    };
+    Some((name.to_string(), size))
+}
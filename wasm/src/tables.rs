@@ -0,0 +1,546 @@
+//! Bottom-up dynamic programming.
+//!
+//! Finds tables allocated with a known size (`[[0] * m for _ in range(n)]`,
+//! `new int[n][m]`, `vec![0; n]`) that a loop then fills from the table's
+//! own entries, as in `dp[i][j] = dp[i - 1][j] + dp[i][j - 1]`. Every entry
+//! is computed once, so filling the table costs its size times the work
+//! done per entry.
+
+use crate::blocks::Block;
+use crate::complexity::Complexity;
+use crate::cost;
+use crate::lexer::{self, Token, TokenKind};
+use crate::loops::{self, Symbols};
+
+/// Operators that store into a table entry.
+const ASSIGNMENTS: &[&str] = &["=", "+=", "-=", "*=", "|=", "&=", "^="];
+
+/// C and C++ element types that may start an array declaration.
+const ELEMENT_TYPES: &[&str] = &[
+    "int", "long", "short", "char", "bool", "float", "double", "unsigned", "signed",
+];
+
+/// A dynamic programming table and the cost of filling it.
+#[derive(Debug, Clone)]
+pub struct Table {
+    pub name: String,
+    pub line: usize,
+    /// Size of each dimension, outermost first.
+    pub dimensions: Vec<Complexity>,
+    /// Work per entry: loops around a fill that do not pick the entry
+    /// being written, such as the loop over coins in coin change.
+    pub transition: Complexity,
+}
+
+impl Table {
+    /// Number of entries.
+    pub fn size(&self) -> Complexity {
+        self.dimensions
+            .iter()
+            .fold(Complexity::constant(), |size, dimension| {
+                size.times(dimension)
+            })
+    }
+
+    /// Time to fill every entry.
+    pub fn time(&self) -> Complexity {
+        self.size().times(&self.transition)
+    }
+
+    /// Summary such as `2-D DP table 'dp' (line 3) with O(n·m) entries,
+    /// O(1) work per entry: time O(n·m), space O(n·m)`.
+    pub fn describe(&self) -> String {
+        format!(
+            "{}-D DP table '{}' (line {}) with {} entries, {} work per entry: time {}, space {}",
+            self.dimensions.len(),
+            self.name,
+            self.line,
+            self.size(),
+            self.transition,
+            self.time(),
+            self.size()
+        )
+    }
+}
+
+/// An array allocated with a size, such as `dp = [0] * (n + 1)`.
+#[derive(Debug, Clone)]
+pub struct Allocation<'a> {
+    pub name: String,
+    pub line: usize,
+    /// Size expression of each dimension, outermost first.
+    dimensions: Vec<&'a [Token]>,
+}
+
+impl Allocation<'_> {
+    /// Number of elements allocated.
+    pub fn size(&self, symbols: &mut Symbols) -> Complexity {
+        self.dimensions
+            .iter()
+            .fold(Complexity::constant(), |size, expression| {
+                size.times(&dimension(expression, symbols))
+            })
+    }
+}
+
+/// A store into a table entry that reads other entries of the same table.
+struct Fill<'a> {
+    index: usize,
+    /// Identifiers used in the subscripts of the entry written.
+    subscripts: Vec<&'a str>,
+    /// Right-hand side of the assignment.
+    value: &'a [Token],
+}
+
+/// Arrays allocated in `function` with a size, in source order. Rows given
+/// their own allocation (`dp[i] = new int[m]`) add a dimension to the array.
+pub fn allocations<'a>(tokens: &'a [Token], function: &Block) -> Vec<Allocation<'a>> {
+    let body = function.header.end.max(function.span.start)..function.span.end;
+    let mut allocated: Vec<Allocation> = Vec::new();
+
+    for i in body.clone() {
+        let token = &tokens[i];
+
+        // `int dp[n + 1][m + 1];`, `vector<vector<int>> dp(n, vector<int>(m));`
+        if token.kind == TokenKind::Identifier && i > body.start {
+            let previous = &tokens[i - 1];
+            let dimensions = match tokens.get(i + 1) {
+                Some(next) if next.is_punct("[") && is_element_type(previous) => {
+                    Some(bracket_dimensions(&tokens[i + 1..body.end]))
+                }
+                Some(next)
+                    if next.is_punct("(")
+                        && (previous.is_punct(">") || previous.is_punct(">>"))
+                        && declares_vector(tokens, i) =>
+                {
+                    Some(vector_dimensions(&loops::call_arguments(tokens, i + 1)))
+                }
+                _ => None,
+            };
+            if let Some(dimensions) = dimensions.filter(|d| !d.is_empty()) {
+                allocated.push(Allocation {
+                    name: token.text.clone(),
+                    line: token.line,
+                    dimensions,
+                });
+            }
+            continue;
+        }
+
+        if !token.is_punct("=") && !token.is_punct(":=") {
+            continue;
+        }
+        let end = statement_end(tokens, i + 1, body.end);
+        let Some(dimensions) = dimensions(&tokens[i + 1..end]).filter(|d| !d.is_empty()) else {
+            continue;
+        };
+        let Some((name, subscripts, _)) = assignment_target(tokens, body.start, i) else {
+            continue;
+        };
+        match allocated
+            .iter_mut()
+            .rev()
+            .find(|allocation| allocation.name == name)
+        {
+            // `dp[i] = new int[m]` gives each row of `dp` its length
+            Some(existing) if subscripts.len() == existing.dimensions.len() => {
+                existing.dimensions.extend(dimensions);
+            }
+            _ if subscripts.is_empty() => allocated.push(Allocation {
+                name: name.to_string(),
+                line: token.line,
+                dimensions,
+            }),
+            _ => {}
+        }
+    }
+    allocated
+}
+
+/// Tables allocated in `function` and filled inside its loops.
+pub fn tables(tokens: &[Token], function: &Block, symbols: &mut Symbols) -> Vec<Table> {
+    let body = function.header.end.max(function.span.start)..function.span.end;
+    let allocated = allocations(tokens, function);
+    let mut fills: Vec<(&str, Fill)> = Vec::new();
+
+    for i in body.clone() {
+        let token = &tokens[i];
+        if token.kind != TokenKind::Punctuation || !ASSIGNMENTS.contains(&token.text.as_str()) {
+            continue;
+        }
+        let Some((name, subscripts, _)) = assignment_target(tokens, body.start, i) else {
+            continue;
+        };
+        let value = &tokens[i + 1..statement_end(tokens, i + 1, body.end)];
+        let reads_table = value
+            .windows(2)
+            .any(|pair| pair[0].is_ident(name) && pair[1].is_punct("["));
+        if !subscripts.is_empty() && reads_table {
+            fills.push((
+                name,
+                Fill {
+                    index: i,
+                    subscripts,
+                    value,
+                },
+            ));
+        }
+    }
+
+    let mut found = Vec::new();
+    for allocation in allocated {
+        let mut transition: Option<Complexity> = None;
+        for (_, fill) in fills.iter().filter(|(table, _)| *table == allocation.name) {
+            let chain = function.enclosing_loops(fill.index);
+            if chain.is_empty() {
+                continue;
+            }
+            let work = fill_transition(tokens, &chain, fill, symbols);
+            transition = Some(match transition {
+                Some(current) => current.max(work),
+                None => work,
+            });
+        }
+        let Some(transition) = transition else {
+            continue;
+        };
+        let dimensions = allocation
+            .dimensions
+            .iter()
+            .map(|expression| dimension(expression, symbols))
+            .collect();
+        found.push(Table {
+            name: allocation.name,
+            line: allocation.line,
+            dimensions,
+            transition,
+        });
+    }
+    found
+}
+
+/// Elements allocated by the expression `tokens`, if it allocates an array
+/// of known size: `O(n·m)` for `new int[n][m]`.
+pub fn allocation_size(tokens: &[Token], symbols: &mut Symbols) -> Option<Complexity> {
+    let dimensions = dimensions(tokens).filter(|d| !d.is_empty())?;
+    Some(
+        dimensions
+            .iter()
+            .fold(Complexity::constant(), |size, expression| {
+                size.times(&dimension(expression, symbols))
+            }),
+    )
+}
+
+/// Size expressions of an allocation, outermost first, or `None` when
+/// `tokens` does not allocate an array.
+fn dimensions(tokens: &[Token]) -> Option<Vec<&[Token]>> {
+    let tokens = skip_arrow_body(tokens);
+    let first = tokens.first()?;
+
+    // `[0] * n`, `[[0] * m for _ in range(n)]`, `[[0; m]; n]`
+    if first.is_punct("[") {
+        let close = lexer::matching_close(tokens, 0)?;
+        let inner = &tokens[1..close];
+        let rest = &tokens[close + 1..];
+        if let [star, size @ ..] = rest
+            && star.is_punct("*")
+            && !size.is_empty()
+        {
+            return Some(nested(size, inner));
+        }
+        if let [element, size] = loops::split_top_level(inner, ";")[..] {
+            return Some(nested(size, element));
+        }
+        let comprehension = inner.iter().position(|t| t.is_keyword("for"))?;
+        let range = inner[comprehension..]
+            .iter()
+            .position(|t| t.is_ident("range"))
+            .map(|p| comprehension + p)?;
+        let arguments = loops::call_arguments(inner, range + 1);
+        let size = arguments.get(1).or(arguments.first())?;
+        return Some(nested(size, &inner[..comprehension]));
+    }
+
+    // `vec![0; n]`
+    if first.is_ident("vec") && tokens.get(1).is_some_and(|t| t.is_punct("!")) {
+        return dimensions(&tokens[2..]);
+    }
+
+    // `make([]int, n)`
+    if first.is_ident("make") && tokens.get(1).is_some_and(|t| t.is_punct("(")) {
+        let arguments = loops::call_arguments(tokens, 1);
+        return arguments.get(1).map(|size| vec![*size]);
+    }
+
+    // `Array.from({ length: n }, () => new Array(m).fill(0))`
+    if first.is_ident("Array")
+        && tokens.get(1).is_some_and(|t| t.is_punct("."))
+        && tokens.get(2).is_some_and(|t| t.is_ident("from"))
+    {
+        let arguments = loops::call_arguments(tokens, 3);
+        let options = arguments.first()?;
+        let length = options.iter().position(|t| t.is_ident("length"))?;
+        let size = options.get(length + 2..options.len().saturating_sub(1))?;
+        let element = arguments.get(1).copied().unwrap_or_default();
+        return Some(nested(size, element));
+    }
+
+    // `new Array(n).fill(0)`, `Array(n).fill().map(() => Array(m).fill(0))`
+    let start = usize::from(first.is_keyword("new") || first.is_ident("new"));
+    if tokens.get(start).is_some_and(|t| t.is_ident("Array"))
+        && tokens.get(start + 1).is_some_and(|t| t.is_punct("("))
+    {
+        let close = lexer::matching_close(tokens, start + 1)?;
+        let arguments = loops::call_arguments(tokens, start + 1);
+        let [size] = arguments[..] else {
+            return None;
+        };
+        let element = chained_element(&tokens[close + 1..]);
+        return Some(nested(size, element));
+    }
+
+    // `new int[n][m]`, `new int[n, m]`
+    if start == 1 {
+        let open = tokens
+            .iter()
+            .position(|t| t.is_punct("[") || t.is_punct("("))?;
+        if tokens[open].is_punct("[") {
+            return Some(bracket_dimensions(&tokens[open..]));
+        }
+        return None;
+    }
+
+    // `vector<int>(n, 0)`
+    if first.is_ident("vector") || first.is_ident("std") {
+        let open = tokens.iter().position(|t| t.is_punct("("))?;
+        return Some(vector_dimensions(&loops::call_arguments(tokens, open)));
+    }
+
+    None
+}
+
+/// `size` followed by the dimensions of each element.
+fn nested<'a>(size: &'a [Token], element: &'a [Token]) -> Vec<&'a [Token]> {
+    let mut found = vec![size];
+    found.extend(dimensions(element).unwrap_or_default());
+    found
+}
+
+/// What fills an `Array(n)`: the argument of `.fill(...)` or the body of
+/// the callback passed to `.map(...)`.
+fn chained_element(tokens: &[Token]) -> &[Token] {
+    let mut element: &[Token] = &[];
+    let mut i = 0;
+    while let [dot, method, open, ..] = &tokens[i..] {
+        if !dot.is_punct(".") || !open.is_punct("(") {
+            break;
+        }
+        let Some(close) = lexer::matching_close(tokens, i + 2) else {
+            break;
+        };
+        if method.is_ident("fill") || method.is_ident("map") {
+            let argument = &tokens[i + 3..close];
+            if !argument.is_empty() {
+                element = argument;
+            }
+        }
+        i = close + 1;
+    }
+    element
+}
+
+/// Drops a leading `() =>` or `_ => { return` so a callback reads as the
+/// value it returns.
+fn skip_arrow_body(tokens: &[Token]) -> &[Token] {
+    let parameters_end = match tokens.first() {
+        Some(open) if open.is_punct("(") => lexer::matching_close(tokens, 0).map(|close| close + 1),
+        Some(first) if first.kind == TokenKind::Identifier => Some(1),
+        _ => None,
+    };
+    let Some(arrow) =
+        parameters_end.filter(|&end| tokens.get(end).is_some_and(|t| t.is_punct("=>")))
+    else {
+        return tokens;
+    };
+    let mut body = &tokens[arrow + 1..];
+    while let [first, rest @ ..] = body
+        && (first.is_punct("{") || first.is_keyword("return"))
+    {
+        body = rest;
+    }
+    body
+}
+
+/// Sizes inside consecutive `[n][m]` groups, or the comma separated sizes
+/// of a single `[n, m]` group.
+fn bracket_dimensions(tokens: &[Token]) -> Vec<&[Token]> {
+    let mut found = Vec::new();
+    let mut open = 0;
+    while tokens.get(open).is_some_and(|t| t.is_punct("[")) {
+        let Some(close) = lexer::matching_close(tokens, open) else {
+            break;
+        };
+        let inner = &tokens[open + 1..close];
+        if inner.is_empty() {
+            break;
+        }
+        found.extend(loops::split_top_level(inner, ","));
+        open = close + 1;
+    }
+    found
+}
+
+/// Sizes of `vector<vector<int>>(n, vector<int>(m))` from its arguments.
+fn vector_dimensions<'a>(arguments: &[&'a [Token]]) -> Vec<&'a [Token]> {
+    match arguments {
+        [size, element, ..] => nested(size, element),
+        [size] => vec![*size],
+        [] => Vec::new(),
+    }
+}
+
+/// Whether the declaration of the identifier at `index` has a
+/// `vector<...>` type.
+fn declares_vector(tokens: &[Token], index: usize) -> bool {
+    tokens[..index]
+        .iter()
+        .rev()
+        .take_while(|t| t.line == tokens[index].line && !t.is_punct(";") && !t.is_punct("{"))
+        .any(|t| t.is_ident("vector"))
+}
+
+fn is_element_type(token: &Token) -> bool {
+    ELEMENT_TYPES.contains(&token.text.as_str())
+        || (token.kind == TokenKind::Identifier && token.text.ends_with("_t"))
+}
+
+/// The name, subscript identifiers and name index on the left of the
+/// assignment at `operator`: `dp` and `[i, j]` for `dp[i][j + 1] =`, `dp`
+/// for `let mut dp: Vec<u64> =`.
+pub fn assignment_target(
+    tokens: &[Token],
+    start: usize,
+    operator: usize,
+) -> Option<(&str, Vec<&str>, usize)> {
+    let mut end = operator.checked_sub(1).filter(|&i| i >= start)?;
+    if tokens[end].is_punct(":") {
+        end = end.checked_sub(1).filter(|&i| i >= start)?;
+    }
+
+    // Walk back over `[...]` groups to the name they subscript
+    let mut subscripts = Vec::new();
+    while tokens[end].is_punct("]") {
+        let mut depth = 0usize;
+        let mut open = end;
+        loop {
+            match tokens[open].text.as_str() {
+                "]" if tokens[open].kind == TokenKind::Punctuation => depth += 1,
+                "[" if tokens[open].kind == TokenKind::Punctuation => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            open = open.checked_sub(1).filter(|&i| i >= start)?;
+        }
+        subscripts.extend(
+            tokens[open + 1..end]
+                .iter()
+                .filter(|t| t.kind == TokenKind::Identifier)
+                .map(|t| t.text.as_str()),
+        );
+        end = open.checked_sub(1).filter(|&i| i >= start)?;
+    }
+    if tokens[end].kind == TokenKind::Identifier {
+        return Some((tokens[end].text.as_str(), subscripts, end));
+    }
+
+    // `let dp: Vec<Vec<i64>> =`, `dp: list[int] =`
+    if !subscripts.is_empty() {
+        return None;
+    }
+    let line = tokens[operator].line;
+    (start..end)
+        .rev()
+        .take_while(|&i| tokens[i].line == line)
+        .find(|&i| tokens[i].kind == TokenKind::Identifier && tokens[i + 1].is_punct(":"))
+        .map(|i| (tokens[i].text.as_str(), Vec::new(), i))
+}
+
+/// End of the statement starting at `start`: the next `;` or line break
+/// outside brackets.
+pub fn statement_end(tokens: &[Token], start: usize, limit: usize) -> usize {
+    let mut i = start;
+    while i < limit {
+        let token = &tokens[i];
+        if token.is_punct(";")
+            || token.is_punct("}")
+            || (i > start && token.line != tokens[i - 1].line)
+        {
+            return i;
+        }
+        if token.is_punct("(") || token.is_punct("[") || token.is_punct("{") {
+            match lexer::matching_close(tokens, i) {
+                Some(close) if close < limit => i = close,
+                _ => return limit,
+            }
+        }
+        i += 1;
+    }
+    limit
+}
+
+/// Work per entry of `fill`: the loops around it whose index does not
+/// appear in the entry written, and any generator in its value such as
+/// `min(dp[i - c] for c in coins)`.
+fn fill_transition(
+    tokens: &[Token],
+    chain: &[&Block],
+    fill: &Fill,
+    symbols: &mut Symbols,
+) -> Complexity {
+    let mut transition = Complexity::constant();
+    for (block, iterations) in chain
+        .iter()
+        .zip(cost::loop_iterations(tokens, chain, symbols))
+    {
+        let picks_entry = loops::loop_index(tokens, block)
+            .is_some_and(|index| fill.subscripts.contains(&index.as_str()));
+        if !picks_entry {
+            transition = transition.times(&iterations);
+        }
+    }
+
+    for (i, token) in fill.value.iter().enumerate() {
+        if !token.is_keyword("for") {
+            continue;
+        }
+        let Some(within) = fill.value[i..].iter().position(|t| t.is_keyword("in")) else {
+            continue;
+        };
+        let iterable = &fill.value[i + within + 1..];
+        let end = iterable
+            .iter()
+            .position(|t| t.is_punct(")") || t.is_punct("]") || t.is_keyword("if"))
+            .unwrap_or(iterable.len());
+        if let Some(source) = loops::iterable_source(&iterable[..end]) {
+            transition = transition.times(&Complexity::variable(&symbols.symbol_for(&source)));
+        }
+    }
+    transition
+}
+
+/// Size of one dimension: `O(1)` for a literal, otherwise the variable it
+/// is measured by.
+fn dimension(tokens: &[Token], symbols: &mut Symbols) -> Complexity {
+    if !tokens.iter().any(|t| t.kind == TokenKind::Identifier) {
+        return Complexity::constant();
+    }
+    match loops::value_source(tokens) {
+        Some(source) => Complexity::variable(&symbols.symbol_for(&source)),
+        None => Complexity::variable(&symbols.primary()),
+    }
+}
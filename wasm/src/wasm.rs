@@ -0,0 +1,131 @@
+//! JavaScript bindings, built with the `wasm` feature.
+
+use crate::{ComplexityAnalyzer, SourceFile, project};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console)]
+    fn log(s: &str);
+}
+
+macro_rules! console_log {
+    ($($t:tt)*) => {
+        log(&format_args!($($t)*).to_string())
+    }
+}
+
+/// Converts `value` to plain JavaScript objects and arrays, with maps as
+/// objects rather than `Map`s, so that `JSON.stringify` keeps them.
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsValue> {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| {
+            console_log!("Serialization error: {}", e);
+            JsValue::from_str("Failed to serialize result")
+        })
+}
+
+#[wasm_bindgen]
+pub fn analyze_complexity(code: &str, language: &str) -> Result<JsValue, JsValue> {
+    // Early validation
+    if code.is_empty() {
+        return Err(JsValue::from_str("Empty code provided"));
+    }
+
+    if code.len() > 500_000 {
+        // 500KB limit
+        return Err(JsValue::from_str("Code too large to analyze"));
+    }
+
+    console_log!(
+        "Analyzing complexity for {} code ({} chars)",
+        language,
+        code.len()
+    );
+
+    let analyzer = ComplexityAnalyzer::new(language);
+    let result = analyzer.analyze(code);
+
+    to_js(&result)
+}
+
+/// Analyzes a set of `{ path, language, source }` files together, so that
+/// calls into functions of other files are charged what those cost.
+#[wasm_bindgen]
+pub fn analyze_project(files: JsValue) -> Result<JsValue, JsValue> {
+    let files: Vec<SourceFile> = serde_wasm_bindgen::from_value(files)
+        .map_err(|e| JsValue::from_str(&format!("Invalid project files: {}", e)))?;
+
+    // Early validation
+    if files.is_empty() {
+        return Err(JsValue::from_str("No files provided"));
+    }
+
+    let total: usize = files.iter().map(|file| file.source.len()).sum();
+    if total > 5_000_000 {
+        // 5MB limit across the project
+        return Err(JsValue::from_str("Project too large to analyze"));
+    }
+
+    console_log!(
+        "Analyzing complexity for project of {} files ({} chars)",
+        files.len(),
+        total
+    );
+
+    let result = project::analyze_sources(&files);
+
+    to_js(&result)
+}
+
+/// Analyzes two versions of a file and returns the functions added,
+/// removed, improved and regressed between them, with their old and new
+/// complexity.
+#[wasm_bindgen]
+pub fn diff_complexity(old_code: &str, new_code: &str, language: &str) -> Result<JsValue, JsValue> {
+    // Early validation
+    if old_code.len() > 500_000 || new_code.len() > 500_000 {
+        // 500KB limit per version
+        return Err(JsValue::from_str("Code too large to analyze"));
+    }
+
+    console_log!(
+        "Comparing complexity of {} code ({} -> {} chars)",
+        language,
+        old_code.len(),
+        new_code.len()
+    );
+
+    let analyzer = ComplexityAnalyzer::new(language);
+    let diff = analyzer.diff(old_code, new_code);
+
+    to_js(&diff)
+}
+
+#[wasm_bindgen]
+pub fn get_supported_languages() -> Vec<JsValue> {
+    vec![
+        JsValue::from_str("javascript"),
+        JsValue::from_str("typescript"),
+        JsValue::from_str("python"),
+        JsValue::from_str("java"),
+        JsValue::from_str("c"),
+        JsValue::from_str("cpp"),
+        JsValue::from_str("rust"),
+    ]
+}
+
+#[wasm_bindgen]
+pub fn init() {
+    console_log!("Rust-WASM Big O Analyzer initialized");
+}
+
+#[wasm_bindgen(start)]
+pub fn main() {
+    #[cfg(feature = "console_error_panic_hook")]
+    console_error_panic_hook::set_once();
+
+    console_log!("WASM module loaded successfully");
+}